@@ -1,4 +1,5 @@
 #include <windows.h>
+#include <tlhelp32.h>
 #include <stdlib.h>
 
 UINT32 ExtractIconImpl(LPCWSTR path, UINT8** output_buf, UINT64* width, UINT64* height, UINT64* bwidth) {
@@ -76,4 +77,180 @@ UINT32 GetScreenSize(UINT64* width, UINT64* height) {
     *width = w;
     *height = h;
     return 0;
-}
\ No newline at end of file
+}
+
+UINT32 AttachParentConsole() {
+    // Built with the windows subsystem, so there is no console until we borrow the caller's
+    if (!AttachConsole(ATTACH_PARENT_PROCESS)) {
+        return GetLastError();
+    }
+    return 0;
+}
+
+typedef HRESULT (WINAPI *GetDpiForMonitorFn)(HMONITOR, int, UINT*, UINT*);
+
+// Effective DPI of a monitor, GetDpiForMonitor lives in shcore.dll which older systems lack
+static UINT MonitorDpi(HMONITOR monitor) {
+    static GetDpiForMonitorFn get_dpi = NULL;
+    static BOOL loaded = FALSE;
+    if (!loaded) {
+        HMODULE shcore = LoadLibraryW(L"shcore.dll");
+        if (shcore != NULL) {
+            get_dpi = (GetDpiForMonitorFn)GetProcAddress(shcore, "GetDpiForMonitor");
+        }
+        loaded = TRUE;
+    }
+
+    UINT dpi_x = USER_DEFAULT_SCREEN_DPI;
+    UINT dpi_y = USER_DEFAULT_SCREEN_DPI;
+    // 0 is MDT_EFFECTIVE_DPI
+    if (get_dpi == NULL || FAILED(get_dpi(monitor, 0, &dpi_x, &dpi_y))) {
+        return USER_DEFAULT_SCREEN_DPI;
+    }
+    return dpi_x;
+}
+
+static UINT32 MonitorWorkArea(HMONITOR monitor, INT64* left, INT64* top, INT64* right, INT64* bottom, UINT32* dpi) {
+    MONITORINFO info;
+    info.cbSize = sizeof(info);
+    if (monitor == NULL || !GetMonitorInfoW(monitor, &info)) {
+        return GetLastError();
+    }
+    *left = info.rcWork.left;
+    *top = info.rcWork.top;
+    *right = info.rcWork.right;
+    *bottom = info.rcWork.bottom;
+    *dpi = MonitorDpi(monitor);
+    return 0;
+}
+
+UINT32 GetCursorPosition(INT64* x, INT64* y) {
+    POINT pt;
+    if (!GetCursorPos(&pt)) {
+        return GetLastError();
+    }
+    *x = pt.x;
+    *y = pt.y;
+    return 0;
+}
+
+// Work area (screen without taskbars) of the monitor nearest to a point
+UINT32 GetWorkAreaAt(INT64 x, INT64 y, INT64* left, INT64* top, INT64* right, INT64* bottom, UINT32* dpi) {
+    POINT pt = { (LONG)x, (LONG)y };
+    return MonitorWorkArea(MonitorFromPoint(pt, MONITOR_DEFAULTTONEAREST), left, top, right, bottom, dpi);
+}
+
+// Work area of the monitor showing the foreground window, where the link was most likely clicked
+UINT32 GetActiveWorkArea(INT64* left, INT64* top, INT64* right, INT64* bottom, UINT32* dpi) {
+    HMONITOR monitor;
+    HWND foreground = GetForegroundWindow();
+    if (foreground != NULL) {
+        monitor = MonitorFromWindow(foreground, MONITOR_DEFAULTTONEAREST);
+    } else {
+        POINT pt;
+        if (!GetCursorPos(&pt)) {
+            return GetLastError();
+        }
+        monitor = MonitorFromPoint(pt, MONITOR_DEFAULTTONEAREST);
+    }
+    return MonitorWorkArea(monitor, left, top, right, bottom, dpi);
+}
+
+// Full image path of a process, as far as our rights allow
+static UINT32 ProcessImagePath(DWORD pid, LPWSTR buffer, UINT32 size) {
+    HANDLE process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+    if (process == NULL) {
+        return GetLastError();
+    }
+    DWORD length = size;
+    UINT32 result = 0;
+    if (!QueryFullProcessImageNameW(process, 0, buffer, &length)) {
+        result = GetLastError();
+    }
+    CloseHandle(process);
+    return result;
+}
+
+// Entry of a process in a snapshot, FALSE when it is gone
+static BOOL FindProcess(HANDLE snapshot, DWORD pid, PROCESSENTRY32W* entry) {
+    entry->dwSize = sizeof(*entry);
+    if (!Process32FirstW(snapshot, entry)) {
+        return FALSE;
+    }
+    do {
+        if (entry->th32ProcessID == pid) {
+            return TRUE;
+        }
+    } while (Process32NextW(snapshot, entry));
+    return FALSE;
+}
+
+// Creation time of a process, FALSE when it can't be queried
+static BOOL ProcessCreationTime(DWORD pid, ULARGE_INTEGER* created) {
+    HANDLE process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+    if (process == NULL) {
+        return FALSE;
+    }
+    FILETIME creation, exited, kernel, user;
+    BOOL result = GetProcessTimes(process, &creation, &exited, &kernel, &user);
+    CloseHandle(process);
+    if (result) {
+        created->LowPart = creation.dwLowDateTime;
+        created->HighPart = creation.dwHighDateTime;
+    }
+    return result;
+}
+
+// Image paths of up to `max_depth` ancestors of this process from a single snapshot, parent first.
+// Path i starts at `buffer + i * slot` and is cut to `slot` characters, `count` receives how many were found.
+UINT32 GetProcessAncestry(LPWSTR buffer, UINT32 slot, UINT32 max_depth, UINT32* count) {
+    *count = 0;
+    DWORD pid = GetCurrentProcessId();
+    ULARGE_INTEGER child_created;
+    if (!ProcessCreationTime(pid, &child_created)) {
+        return GetLastError();
+    }
+    HANDLE snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+    if (snapshot == INVALID_HANDLE_VALUE) {
+        return GetLastError();
+    }
+
+    PROCESSENTRY32W entry;
+    while (*count < max_depth && FindProcess(snapshot, pid, &entry)) {
+        DWORD parent = entry.th32ParentProcessID;
+        if (parent == 0 || parent == pid || !FindProcess(snapshot, parent, &entry)) {
+            break;
+        }
+        // A parent that exited leaves its id free for reuse. A process created after its child
+        // can't have started it, and one that can't be checked isn't trusted either.
+        ULARGE_INTEGER parent_created;
+        if (!ProcessCreationTime(parent, &parent_created)
+            || parent_created.QuadPart > child_created.QuadPart) {
+            break;
+        }
+        child_created = parent_created;
+        LPWSTR path = buffer + (SIZE_T)*count * slot;
+        // The file name from the snapshot still tells what it is when the full path is unreadable
+        if (ProcessImagePath(parent, path, slot) != 0) {
+            lstrcpynW(path, entry.szExeFile, slot);
+        }
+        (*count)++;
+        pid = parent;
+    }
+    CloseHandle(snapshot);
+    return 0;
+}
+
+// Image path of the process owning the foreground window, our own window isn't up yet
+UINT32 GetForegroundProcessImage(LPWSTR buffer, UINT32 size) {
+    HWND foreground = GetForegroundWindow();
+    if (foreground == NULL) {
+        return ERROR_NOT_FOUND;
+    }
+    DWORD pid = 0;
+    GetWindowThreadProcessId(foreground, &pid);
+    if (pid == 0) {
+        return GetLastError();
+    }
+    return ProcessImagePath(pid, buffer, size);
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+// Names hosts files map to themselves, never worth blocking
+const LOCAL_NAMES: &[&str] = &[
+    "localhost",
+    "localhost.localdomain",
+    "local",
+    "broadcasthost",
+    "ip6-localhost",
+    "ip6-loopback",
+    "0.0.0.0",
+];
+
+/// What the picker does with a URL whose host is on a blocklist
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockAction {
+    /// Show a warning but allow opening it
+    #[default]
+    Warn,
+    /// Only open it after an explicit "open anyway"
+    Refuse,
+}
+
+/// Blocklisted host and where it was listed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockMatch {
+    pub domain: String, // Entry that matched, the host itself or a parent domain
+    pub list: String,   // File the entry came from
+}
+
+/// Domains from local blocklist files
+#[derive(Clone, Default)]
+pub struct Blocklist {
+    domains: HashMap<String, String>, // Domain to the file it came from
+}
+
+impl Blocklist {
+    /// Load every list, paths are relative to the executable. Unreadable files are logged and skipped.
+    pub fn load(paths: &[String]) -> Self {
+        let mut blocklist = Self::default();
+        for path in paths {
+            let full_path = crate::utils::path_to(path);
+            match fs::read_to_string(&full_path) {
+                Ok(content) => {
+                    let before = blocklist.domains.len();
+                    blocklist.add(&content, path);
+                    log::info!(
+                        "Loaded {} domains from blocklist {}",
+                        blocklist.domains.len() - before,
+                        full_path.display()
+                    );
+                }
+                Err(e) => log::error!("Failed to read blocklist {}: {e}", full_path.display()),
+            }
+        }
+        blocklist
+    }
+
+    /// Add the domains of a list in hosts file (`0.0.0.0 example.com`) or plain format, one per line
+    fn add(&mut self, content: &str, list: &str) {
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut fields = line.split_whitespace().peekable();
+            // Hosts file lines start with an address, plain lists with the domain
+            if fields
+                .peek()
+                .is_some_and(|f| f.parse::<std::net::IpAddr>().is_ok())
+            {
+                fields.next();
+            }
+            for domain in fields {
+                let domain = domain.trim_end_matches('.').to_lowercase();
+                if !domain.is_empty() && !LOCAL_NAMES.contains(&domain.as_str()) {
+                    self.domains
+                        .entry(domain)
+                        .or_insert_with(|| list.to_string());
+                }
+            }
+        }
+    }
+
+    /// Entry matching `host` or one of its parent domains
+    pub fn check(&self, host: &str) -> Option<BlockMatch> {
+        let host = host.trim_end_matches('.').to_lowercase();
+        let mut domain = host.as_str();
+        loop {
+            if let Some(list) = self.domains.get(domain) {
+                return Some(BlockMatch {
+                    domain: domain.to_string(),
+                    list: list.clone(),
+                });
+            }
+            domain = domain.split_once('.')?.1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hosts_and_plain_formats() {
+        let mut blocklist = Blocklist::default();
+        blocklist.add(
+            "# Phishing\n127.0.0.1 localhost\n0.0.0.0 evil.example tracker.example # ads\n::1 ip6-localhost\n",
+            "hosts.txt",
+        );
+        blocklist.add("bad.test\n\n  Worse.Test.  \n", "plain.txt");
+
+        assert_eq!(
+            blocklist.check("evil.example"),
+            Some(BlockMatch {
+                domain: "evil.example".to_string(),
+                list: "hosts.txt".to_string(),
+            })
+        );
+        assert!(blocklist.check("tracker.example").is_some());
+        assert_eq!(blocklist.check("worse.test").unwrap().list, "plain.txt");
+        assert!(blocklist.check("localhost").is_none());
+        assert!(blocklist.check("example").is_none());
+    }
+
+    #[test]
+    fn subdomains_match_their_parent() {
+        let mut blocklist = Blocklist::default();
+        blocklist.add("bad.test\n", "plain.txt");
+        assert_eq!(
+            blocklist.check("login.Bad.Test").unwrap().domain,
+            "bad.test"
+        );
+        assert!(blocklist.check("notbad.test").is_none());
+        assert!(blocklist.check("test").is_none());
+    }
+}
@@ -0,0 +1,232 @@
+//! Binary layout of a cache entry, all integers little-endian:
+//!
+//! | field       | size | notes                                   |
+//! |-------------|------|-----------------------------------------|
+//! | magic       | 4    | `UPXI`                                  |
+//! | version     | 2    | `FORMAT_VERSION`                        |
+//! | timestamp   | 8    | unix milliseconds of the extraction     |
+//! | path        | 4+n  | length prefixed UTF-8 source path       |
+//! | fingerprint | 1+16 | presence flag, file size, modified time |
+//! | images      | 2+.. | count, then width, height, length, PNG  |
+//! | checksum    | 4    | CRC32 of everything before it           |
+//!
+//! Entries written before the format existed are a bare native `usize` timestamp
+//! followed by PNG bytes, `decode` still reads those through `decode_legacy`.
+
+use super::{CacheEntry, Fingerprint};
+use image::RgbaImage;
+use std::io::Cursor;
+
+const MAGIC: &[u8; 4] = b"UPXI";
+const FORMAT_VERSION: u16 = 1;
+
+/// Serialize a cache entry to bytes
+pub fn encode(entry: &CacheEntry) -> Option<Vec<u8>> {
+    let mut output = Vec::from(*MAGIC);
+    output.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    output.extend_from_slice(&(entry.timestamp as u64).to_le_bytes());
+
+    let path = entry.path.as_bytes();
+    output.extend_from_slice(&(path.len() as u32).to_le_bytes());
+    output.extend_from_slice(path);
+
+    match entry.fingerprint {
+        Some(fingerprint) => {
+            output.push(1);
+            output.extend_from_slice(&fingerprint.size.to_le_bytes());
+            output.extend_from_slice(&fingerprint.modified.to_le_bytes());
+        }
+        None => output.extend_from_slice(&[0; 17]),
+    }
+
+    let images: Vec<&RgbaImage> = entry.image.iter().collect();
+    output.extend_from_slice(&(images.len() as u16).to_le_bytes());
+    for img in images {
+        let png = encode_png(img)?;
+        output.extend_from_slice(&img.width().to_le_bytes());
+        output.extend_from_slice(&img.height().to_le_bytes());
+        output.extend_from_slice(&(png.len() as u32).to_le_bytes());
+        output.extend_from_slice(&png);
+    }
+
+    let checksum = crc32fast::hash(&output);
+    output.extend_from_slice(&checksum.to_le_bytes());
+    Some(output)
+}
+
+/// Deserialize bytes to a cache entry, falling back to the legacy layout
+pub fn decode(data: &[u8]) -> Option<CacheEntry> {
+    if !data.starts_with(MAGIC) {
+        return decode_legacy(data);
+    }
+
+    if data.len() < MAGIC.len() + 4 {
+        log::error!("Cache data too small to contain a checksum");
+        return None;
+    }
+    let (body, checksum) = data.split_at(data.len() - 4);
+    if crc32fast::hash(body) != u32::from_le_bytes(checksum.try_into().ok()?) {
+        log::error!("Cache data checksum mismatch");
+        return None;
+    }
+
+    let mut reader = Reader {
+        data: &body[MAGIC.len()..],
+    };
+    let version = reader.u16()?;
+    if version != FORMAT_VERSION {
+        log::error!("Unsupported cache format version {version}");
+        return None;
+    }
+
+    let timestamp = reader.u64()? as usize;
+    let path_len = reader.u32()? as usize;
+    let path = String::from_utf8(reader.take(path_len)?.to_vec()).ok()?;
+
+    let has_fingerprint = reader.take(1)?[0] != 0;
+    let fingerprint = Fingerprint {
+        size: reader.u64()?,
+        modified: reader.u64()?,
+    };
+
+    let mut image = None;
+    for _ in 0..reader.u16()? {
+        let width = reader.u32()?;
+        let height = reader.u32()?;
+        let png_len = reader.u32()? as usize;
+        let png = reader.take(png_len)?;
+        // Only one size is extracted today, keep the first one that decodes
+        if image.is_none() {
+            image = decode_png(png).filter(|img| img.dimensions() == (width, height));
+        }
+    }
+
+    Some(CacheEntry {
+        image,
+        timestamp,
+        path,
+        fingerprint: has_fingerprint.then_some(fingerprint),
+    })
+}
+
+/// Read an entry written before the versioned format existed
+fn decode_legacy(data: &[u8]) -> Option<CacheEntry> {
+    let timestamp_size = std::mem::size_of::<usize>();
+
+    // Check if data is too small to contain timestamp
+    if data.len() < timestamp_size {
+        log::error!("Cache data too small to contain timestamp");
+        return None;
+    }
+
+    // Extract timestamp
+    let (time_bytes, img_bytes) = data.split_at(timestamp_size);
+    let timestamp = usize::from_le_bytes(time_bytes.try_into().ok()?);
+
+    Some(CacheEntry {
+        image: if img_bytes.is_empty() {
+            None
+        } else {
+            decode_png(img_bytes)
+        },
+        timestamp,
+        path: String::new(),
+        fingerprint: None,
+    })
+}
+
+/// Sequential little-endian reader over a byte slice
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            log::error!("Cache data is truncated");
+            return None;
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+}
+
+pub fn encode_png(img: &RgbaImage) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    let writer = Cursor::new(&mut data);
+    let encoder = image::codecs::png::PngEncoder::new(writer);
+
+    if let Err(e) = img.write_with_encoder(encoder) {
+        log::error!("Failed to encode image: {e}");
+        return None;
+    }
+
+    Some(data)
+}
+
+pub fn decode_png(data: &[u8]) -> Option<RgbaImage> {
+    match image::load_from_memory(data) {
+        Ok(img) => Some(img.to_rgba8()),
+        Err(e) => {
+            log::error!("Failed to decode image from cache: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> CacheEntry {
+        CacheEntry {
+            image: Some(RgbaImage::from_pixel(3, 2, image::Rgba([9, 8, 7, 255]))),
+            timestamp: 1_700_000_000_000,
+            path: "C:\\Program Files\\Browser\\browser.exe".to_string(),
+            fingerprint: Some(Fingerprint {
+                size: 1234,
+                modified: 5678,
+            }),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let decoded = decode(&encode(&entry()).unwrap()).unwrap();
+        assert_eq!(decoded.timestamp, entry().timestamp);
+        assert_eq!(decoded.path, entry().path);
+        assert_eq!(decoded.fingerprint, entry().fingerprint);
+        assert_eq!(decoded.image, entry().image);
+    }
+
+    #[test]
+    fn corrupted_entry_is_rejected() {
+        let mut data = encode(&entry()).unwrap();
+        data[12] ^= 0xff;
+        assert!(decode(&data).is_none());
+    }
+
+    #[test]
+    fn legacy_entry_is_migrated() {
+        let mut data = Vec::from(42usize.to_le_bytes());
+        data.extend(encode_png(entry().image.as_ref().unwrap()).unwrap());
+
+        let decoded = decode(&data).unwrap();
+        assert_eq!(decoded.timestamp, 42);
+        assert_eq!(decoded.image, entry().image);
+        assert!(decoded.fingerprint.is_none());
+    }
+}
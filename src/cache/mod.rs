@@ -0,0 +1,388 @@
+mod format;
+mod store;
+
+pub use store::{CacheBackend, CacheStore};
+
+use crate::utils::{LOCK_RETRY_INTERVAL, LOCK_WAIT};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Error;
+use std::path::PathBuf;
+use std::result::Result;
+
+// Time constants
+const ONE_DAY: usize = 24 * 3600 * 1000; // 24 hours in milliseconds
+const REBUILD_EXPIRE: usize = 5 * 60 * 1000; // 5 minutes in milliseconds
+const REBUILD_TRY_LIMIT: usize = 3;
+
+/// Represents an icon cache entry with timestamp
+#[derive(Clone)]
+pub struct CacheEntry {
+    pub image: Option<RgbaImage>,
+    pub timestamp: usize,
+    pub path: String,                     // Executable the icon was extracted from
+    pub fingerprint: Option<Fingerprint>, // State of that file at extraction, None for legacy entries
+}
+
+/// Size and modification time of an executable, used to notice updated browsers
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Fingerprint {
+    pub size: u64,
+    pub modified: u64, // Unix milliseconds
+}
+
+impl Fingerprint {
+    pub fn of(path: &str) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?;
+        Some(Self {
+            size: metadata.len(),
+            modified: modified.as_millis() as u64,
+        })
+    }
+}
+
+/// Registration of this instance, a file of its own in `cache.instances` locked for its whole lifetime.
+/// Instances register under a shared lock on `cache.lock`, a purge takes that lock exclusively
+/// so nobody can register while it checks that every other registration is gone.
+struct InstanceLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    /// Register this instance, waiting a little if another instance is purging
+    fn acquire() -> Option<Self> {
+        let _gate =
+            crate::utils::lock_with_retry(&crate::utils::cache_lock_path(), true, LOCK_WAIT)?;
+
+        let dir = crate::utils::cache_instances_path();
+        if let Err(e) = fs::create_dir_all(&dir) {
+            log::warn!("Failed to create {}: {e}", dir.display());
+            return None;
+        }
+        let path = dir.join(format!("{}.lock", std::process::id()));
+        let file = crate::utils::lock_with_retry(&path, false, 0)?;
+        // The gate is released when it goes out of scope, the registration stays locked
+        Some(Self { file, path })
+    }
+
+    /// Lock `cache.lock` exclusively if no other instance is registered.
+    /// The registration of this instance is kept, the returned file holds the lock until dropped.
+    fn try_exclusive(&self) -> Option<File> {
+        // Fails while another instance registers or purges
+        let gate = crate::utils::lock_with_retry(&crate::utils::cache_lock_path(), false, 0)?;
+
+        let entries = match fs::read_dir(crate::utils::cache_instances_path()) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("Failed to list cache instances: {e}");
+                return None;
+            }
+        };
+        for entry in entries {
+            let path = entry.ok()?.path();
+            if path == self.path {
+                continue;
+            }
+            let file = OpenOptions::new().write(true).open(&path).ok()?;
+            if file.try_lock().is_err() {
+                return None;
+            }
+            // Left behind by an instance that did not exit cleanly
+            drop(file);
+            if let Err(e) = fs::remove_file(&path) {
+                log::warn!("Failed to remove {}: {e}", path.display());
+            }
+        }
+        Some(gate)
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        if let Err(e) = self.file.unlock() {
+            log::warn!("Failed to unlock {}: {e}", self.path.display());
+        }
+        if let Err(e) = fs::remove_file(&self.path) {
+            log::warn!("Failed to remove {}: {e}", self.path.display());
+        }
+    }
+}
+
+/// Manages icon caching to avoid repeated extraction from executables
+pub struct IconCacheManager {
+    store: Option<Box<dyn CacheStore>>, // Storage handle
+    backend: CacheBackend,              // Backend used to (re)open the store
+    lock: Option<InstanceLock>,         // Held while the cache is in use
+    read_only: bool,                    // Cache is busy in another instance, never touch it
+    rebuild_try: usize,                 // Number of rebuild attempts
+    rebuild_time: usize,                // Last rebuild timestamp
+    expire_time: usize,                 // Cache expiration time in milliseconds
+}
+
+impl IconCacheManager {
+    pub fn new(expire_days: usize, backend: CacheBackend) -> Self {
+        let mut manager = Self {
+            store: None,
+            backend,
+            lock: None,
+            read_only: false,
+            rebuild_try: 0,
+            rebuild_time: crate::utils::get_unix_msec(),
+            expire_time: expire_days * ONE_DAY,
+        };
+
+        // Memory stores are private to this process, nothing to coordinate
+        if backend != CacheBackend::Memory {
+            manager.lock = InstanceLock::acquire();
+            if manager.lock.is_none() {
+                log::warn!("Cache is busy, extracting icons without caching");
+                manager.read_only = true;
+                return manager;
+            }
+        }
+
+        manager.store = manager.open_store();
+        manager
+    }
+
+    /// Open the store, waiting for other instances to release it.
+    /// Switches to read-only mode instead of failing when it stays busy.
+    fn open_store(&mut self) -> Option<Box<dyn CacheStore>> {
+        let start = crate::utils::get_unix_msec();
+        loop {
+            match self.backend.open() {
+                Ok(store) => return Some(store),
+                Err(_)
+                    if self.in_use_elsewhere()
+                        && crate::utils::get_unix_msec() - start < LOCK_WAIT =>
+                {
+                    std::thread::sleep(std::time::Duration::from_millis(LOCK_RETRY_INTERVAL));
+                }
+                Err(e) if self.in_use_elsewhere() => {
+                    log::warn!(
+                        "Cache is in use by another instance ({e}), extracting icons without caching"
+                    );
+                    self.read_only = true;
+                    return None;
+                }
+                Err(e) => {
+                    log::warn!("Failed to open {:?} cache: {e}", self.backend);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Another registered instance may have the store open, so a failure to open it is not an error
+    fn in_use_elsewhere(&self) -> bool {
+        self.lock
+            .as_ref()
+            .is_some_and(|lock| lock.try_exclusive().is_none())
+    }
+
+    /// Create a manager on top of an already opened store
+    #[cfg(test)]
+    fn with_store(store: Box<dyn CacheStore>, expire_days: usize) -> Self {
+        Self {
+            store: Some(store),
+            backend: CacheBackend::Memory,
+            lock: None,
+            read_only: false,
+            rebuild_try: 0,
+            rebuild_time: crate::utils::get_unix_msec(),
+            expire_time: expire_days * ONE_DAY,
+        }
+    }
+
+    /// Get an icon from cache or extract it from the executable
+    pub fn get(&mut self, name: &str, path: &str) -> Option<RgbaImage> {
+        // If a store is available, try to get from cache
+        if let Some(ref mut store) = self.store {
+            match Self::get_from_store(store.as_mut(), self.expire_time, name, path) {
+                Ok(image) => return image,
+                Err(_) => {
+                    // Store error, try to rebuild and extract icon directly
+                    self.force_rebuild();
+                }
+            }
+        } else if !self.read_only {
+            // No store available, try to rebuild
+            self.force_rebuild();
+        }
+
+        // Extract icon directly from file
+        crate::utils::extract_icon(path)
+    }
+
+    /// Try to get an icon from the store, refreshing missing or expired entries
+    fn get_from_store(
+        store: &mut dyn CacheStore,
+        expire_time: usize,
+        name: &str,
+        path: &str,
+    ) -> Result<Option<RgbaImage>, Error> {
+        let now = crate::utils::get_unix_msec();
+        let fingerprint = Fingerprint::of(path);
+        match store.load(name)? {
+            // Not expired and still the same executable, return cached image
+            Some(entry)
+                if entry.timestamp + expire_time >= now
+                    && entry.path == path
+                    && entry.fingerprint == fingerprint =>
+            {
+                Ok(entry.image)
+            }
+            // Legacy entry without metadata, keep the icon and rewrite it in the current format
+            Some(mut entry) if entry.timestamp + expire_time >= now && entry.path.is_empty() => {
+                entry.path = path.to_string();
+                entry.fingerprint = fingerprint;
+                if let Err(e) = store.store(name, &entry) {
+                    log::error!("Failed to migrate cache entry {name}: {e}");
+                }
+                Ok(entry.image)
+            }
+            // Missing, expired, changed or unreadable, extract and update cache
+            _ => Ok(Self::update_cache(store, name, path, now)),
+        }
+    }
+
+    /// Update the cache with a freshly extracted icon and return it
+    fn update_cache(
+        store: &mut dyn CacheStore,
+        name: &str,
+        path: &str,
+        timestamp: usize,
+    ) -> Option<RgbaImage> {
+        let entry = CacheEntry {
+            image: crate::utils::extract_icon(path),
+            timestamp,
+            path: path.to_string(),
+            fingerprint: Fingerprint::of(path),
+        };
+
+        if let Err(e) = store.store(name, &entry) {
+            log::error!("Failed to insert into cache: {e}");
+        }
+
+        entry.image
+    }
+
+    /// Attempt to rebuild the store if it's corrupted
+    pub fn force_rebuild(&mut self) {
+        // First, try to reopen immediately without destructive actions
+        self.store = self.open_store();
+        if self.store.is_some() {
+            self.rebuild_try = 0;
+            self.rebuild_time = crate::utils::get_unix_msec();
+            return;
+        }
+
+        // Held by another instance, it is not ours to rebuild
+        if self.read_only {
+            return;
+        }
+
+        let now = crate::utils::get_unix_msec();
+
+        // If we haven't hit the try limit, just back off and try later
+        if self.rebuild_try < REBUILD_TRY_LIMIT {
+            self.rebuild_try += 1;
+            self.rebuild_time = now;
+            return;
+        }
+
+        // Rate-limit destructive purge attempts
+        if now - self.rebuild_time < REBUILD_EXPIRE {
+            return;
+        }
+
+        // Only purge when no other instance is using the cache
+        let Some(purge_lock) = self.lock.as_ref().and_then(InstanceLock::try_exclusive) else {
+            log::warn!("Cache is in use by another instance, not purging it");
+            self.rebuild_time = now;
+            return;
+        };
+
+        log::info!("Purging and rebuilding cache");
+        self.rebuild_time = now;
+
+        if let Err(e) = self.backend.purge() {
+            log::warn!("Failed to remove cache directory: {e}");
+        }
+        drop(purge_lock);
+
+        match self.backend.open() {
+            Ok(store) => {
+                log::info!("Successfully rebuilt cache");
+                self.store = Some(store);
+                self.rebuild_try = 0;
+            }
+            Err(e) => {
+                log::error!("Failed to rebuild cache: {e}");
+                self.store = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::store::{DirectoryStore, MemoryStore};
+    use super::*;
+
+    fn entry(timestamp: usize) -> CacheEntry {
+        CacheEntry {
+            image: Some(RgbaImage::from_pixel(2, 2, image::Rgba([1, 2, 3, 255]))),
+            timestamp,
+            path: "does-not-exist.exe".to_string(),
+            fingerprint: None,
+        }
+    }
+
+    #[test]
+    fn fresh_entry_is_served_from_store() {
+        let mut store = MemoryStore::default();
+        store
+            .store("browser", &entry(crate::utils::get_unix_msec()))
+            .unwrap();
+
+        let mut cache = IconCacheManager::with_store(Box::new(store), 7);
+        let image = cache.get("browser", "does-not-exist.exe").unwrap();
+        assert_eq!(image.get_pixel(1, 1), &image::Rgba([1, 2, 3, 255]));
+    }
+
+    #[test]
+    fn directory_store_round_trip() {
+        let root = std::env::temp_dir().join(format!("url_proxy_icons_{}", std::process::id()));
+        let mut store = DirectoryStore::open(&root).unwrap();
+        store.store("browser", &entry(42)).unwrap();
+        store
+            .store(
+                "no icon",
+                &CacheEntry {
+                    image: None,
+                    timestamp: 7,
+                    path: String::new(),
+                    fingerprint: None,
+                },
+            )
+            .unwrap();
+
+        // Reopen to make sure everything went through the index on disk
+        let store = DirectoryStore::open(&root).unwrap();
+        let loaded = store.load("browser").unwrap().unwrap();
+        assert_eq!(loaded.timestamp, 42);
+        assert_eq!(loaded.image, entry(42).image);
+        assert!(store.load("no icon").unwrap().unwrap().image.is_none());
+        assert!(store.load("missing").unwrap().is_none());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,233 @@
+use super::{format, CacheEntry, Fingerprint};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::result::Result;
+
+/// Storage used by `IconCacheManager` to persist icon entries
+pub trait CacheStore {
+    /// Load the entry stored under `name`, or `None` if it is missing or unreadable
+    fn load(&self, name: &str) -> Result<Option<CacheEntry>, Error>;
+
+    /// Store an entry under `name`, replacing any previous one
+    fn store(&mut self, name: &str, entry: &CacheEntry) -> Result<(), Error>;
+}
+
+/// Selects which `CacheStore` implementation backs the icon cache
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheBackend {
+    /// Single sled database in the `cache` directory
+    #[default]
+    Sled,
+    /// One PNG file per icon plus a JSON index in the `icons` directory
+    Directory,
+    /// Nothing is persisted, icons are extracted on every run
+    Memory,
+}
+
+impl CacheBackend {
+    /// Open the store for this backend at its default location
+    pub fn open(self) -> Result<Box<dyn CacheStore>, Error> {
+        match self {
+            Self::Sled => Ok(Box::new(SledStore::open(crate::utils::cache_path())?)),
+            Self::Directory => Ok(Box::new(DirectoryStore::open(
+                crate::utils::icon_dir_path(),
+            )?)),
+            Self::Memory => Ok(Box::new(MemoryStore::default())),
+        }
+    }
+
+    /// Remove everything the backend has written to disk
+    pub fn purge(self) -> Result<(), Error> {
+        let path = match self {
+            Self::Sled => crate::utils::cache_path(),
+            Self::Directory => crate::utils::icon_dir_path(),
+            Self::Memory => return Ok(()),
+        };
+        match fs::remove_dir_all(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Entries kept in a sled database in the binary layout of `format`
+pub struct SledStore {
+    db: sled::Db,
+}
+
+impl SledStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        match sled::open(path) {
+            Ok(db) => Ok(Self { db }),
+            Err(e) => Err(Error::other(format!("Database error: {}", e))),
+        }
+    }
+}
+
+impl CacheStore for SledStore {
+    fn load(&self, name: &str) -> Result<Option<CacheEntry>, Error> {
+        match self.db.get(name) {
+            Ok(Some(data)) => {
+                let entry = format::decode(&data);
+                if entry.is_none() {
+                    log::error!("Data from {name} has parsing error");
+                }
+                Ok(entry)
+            }
+            Ok(None) => Ok(None),
+            Err(e) => {
+                log::error!("Database error: {e}");
+                Err(Error::other(format!("Database error: {}", e)))
+            }
+        }
+    }
+
+    fn store(&mut self, name: &str, entry: &CacheEntry) -> Result<(), Error> {
+        let data = format::encode(entry)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Failed to encode cache entry"))?;
+        self.db
+            .insert(name, data)
+            .map_err(|e| Error::other(format!("Failed to insert into cache: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Index record of `DirectoryStore`, the PNG itself lives next to `index.json`
+#[derive(Serialize, Deserialize, Clone)]
+struct IndexEntry {
+    file: Option<String>,
+    timestamp: usize,
+    #[serde(default)]
+    path: String,
+    #[serde(default)]
+    fingerprint: Option<Fingerprint>,
+}
+
+/// Entries kept as plain PNG files with a JSON index, so no database lock is involved
+pub struct DirectoryStore {
+    root: PathBuf,
+    index: BTreeMap<String, IndexEntry>,
+}
+
+impl DirectoryStore {
+    const INDEX_FILE: &'static str = "index.json";
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let root = path.as_ref().to_path_buf();
+        fs::create_dir_all(&root)?;
+
+        let index = Self::read_index(&root)?;
+        Ok(Self { root, index })
+    }
+
+    fn read_index(root: &Path) -> Result<BTreeMap<String, IndexEntry>, Error> {
+        match fs::read_to_string(root.join(Self::INDEX_FILE)) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Failed to parse cache index: {}", e),
+                )
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// File name for an icon, stable for a given entry name
+    fn file_name(name: &str) -> String {
+        // FNV-1a, only needs to be stable and spread names over distinct files
+        let hash = name.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        format!("{hash:016x}.png")
+    }
+
+    /// Write through a temporary file so readers never see a partial file
+    fn write_atomic(&self, file: &str, data: &[u8]) -> Result<(), Error> {
+        let tmp = self.root.join(format!("{file}.tmp"));
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, self.root.join(file))
+    }
+}
+
+impl CacheStore for DirectoryStore {
+    fn load(&self, name: &str) -> Result<Option<CacheEntry>, Error> {
+        let Some(record) = self.index.get(name) else {
+            return Ok(None);
+        };
+
+        let image = match record.file {
+            Some(ref file) => match fs::read(self.root.join(file)) {
+                Ok(data) => format::decode_png(&data),
+                Err(e) => {
+                    log::error!("Failed to read cached icon of {name}: {e}");
+                    return Ok(None);
+                }
+            },
+            None => None,
+        };
+
+        Ok(Some(CacheEntry {
+            image,
+            timestamp: record.timestamp,
+            path: record.path.clone(),
+            fingerprint: record.fingerprint,
+        }))
+    }
+
+    fn store(&mut self, name: &str, entry: &CacheEntry) -> Result<(), Error> {
+        let file = match entry.image {
+            Some(ref img) => {
+                let data = format::encode_png(img)
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Failed to encode icon"))?;
+                let file = Self::file_name(name);
+                self.write_atomic(&file, &data)?;
+                Some(file)
+            }
+            None => None,
+        };
+
+        // Pick up entries other instances wrote since we opened the index
+        if let Ok(index) = Self::read_index(&self.root) {
+            self.index.extend(index);
+        }
+        self.index.insert(
+            name.to_string(),
+            IndexEntry {
+                file,
+                timestamp: entry.timestamp,
+                path: entry.path.clone(),
+                fingerprint: entry.fingerprint,
+            },
+        );
+
+        let index = serde_json::to_vec_pretty(&self.index).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Failed to serialize cache index: {}", e),
+            )
+        })?;
+        self.write_atomic(Self::INDEX_FILE, &index)
+    }
+}
+
+/// Entries kept only for the lifetime of the process
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl CacheStore for MemoryStore {
+    fn load(&self, name: &str) -> Result<Option<CacheEntry>, Error> {
+        Ok(self.entries.get(name).cloned())
+    }
+
+    fn store(&mut self, name: &str, entry: &CacheEntry) -> Result<(), Error> {
+        self.entries.insert(name.to_string(), entry.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use crate::history::{self, HistoryEntry, Routing};
+use crate::mainwindow::BrowserExec;
+use crate::settings::Settings;
+use std::io::{Error, ErrorKind};
+use std::result::Result;
+
+const HISTORY_USAGE: &str = "Usage: url_proxy history [QUERY...] [--open N] [--browser NAME]";
+
+/// `url_proxy history`: list matching history entries, newest first, or re-open one of them
+pub fn history(args: &[String], settings: &Settings) -> Result<(), Error> {
+    crate::utils::attach_console();
+
+    let mut query = Vec::new();
+    let mut open = None;
+    let mut browser = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--open" => {
+                let number = args.next().and_then(|n| n.parse::<usize>().ok());
+                open = Some(number.ok_or_else(|| usage_error("--open needs an entry number"))?);
+            }
+            "--browser" => {
+                let name = args.next();
+                browser = Some(name.ok_or_else(|| usage_error("--browser needs a name"))?);
+            }
+            "-h" | "--help" => {
+                println!("{HISTORY_USAGE}");
+                return Ok(());
+            }
+            _ => query.push(arg.as_str()),
+        }
+    }
+    let query = query.join(" ");
+
+    let entries = history::load();
+    let matches: Vec<&HistoryEntry> = entries.iter().rev().filter(|e| e.matches(&query)).collect();
+
+    let Some(number) = open else {
+        for (i, entry) in matches.iter().enumerate() {
+            let routing = match entry.routing {
+                Routing::Manual => "manual",
+                Routing::Automatic => "auto",
+            };
+            println!(
+                "{:>4}  {}  {:<6}  {:<20}  {}",
+                i + 1,
+                entry.time_string(),
+                routing,
+                entry.browser,
+                entry.url
+            );
+        }
+        return Ok(());
+    };
+
+    let entry = number
+        .checked_sub(1)
+        .and_then(|i| matches.get(i))
+        .ok_or_else(|| usage_error(&format!("No history entry {number}")))?;
+    let name = browser.unwrap_or(&entry.browser);
+    let info = settings
+        .handlers_for(&entry.url)
+        .iter()
+        .find(|b| b.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| usage_error(&format!("No browser named {name}")))?;
+
+    let policy = settings.link_policy();
+    let host = crate::link::canonicalize(&entry.url, &policy)
+        .ok()
+        .and_then(|url| crate::link::host(&url));
+    let blocklist = crate::blocklist::Blocklist::load(&settings.blocklists);
+    if let Some(found) = host.and_then(|host| blocklist.check(&host)) {
+        let message = format!("{} is on blocklist {}", found.domain, found.list);
+        if settings.blocklist_action == crate::blocklist::BlockAction::Refuse {
+            log::warn!("Refused {}, {message}", entry.url);
+            return Err(Error::new(ErrorKind::PermissionDenied, message));
+        }
+        log::warn!("Opening {} although {message}", entry.url);
+        eprintln!("Warning: {message}");
+    }
+
+    let url = BrowserExec::from_info(info).spawn(&entry.url, &policy)?;
+    log::info!("Reopened {url} in {}", info.name);
+    println!("Opened {url} in {}", info.name);
+
+    let reopened = HistoryEntry::new(&url, &info.name, Routing::Manual);
+    if let Err(e) = history::append(&reopened, settings.history_limit) {
+        log::error!("Failed to write history: {e}");
+    }
+    Ok(())
+}
+
+fn usage_error(message: &str) -> Error {
+    eprintln!("{message}\n{HISTORY_USAGE}");
+    Error::new(ErrorKind::InvalidInput, message.to_string())
+}
@@ -0,0 +1,73 @@
+// Scoring weights
+const MATCH_SCORE: i32 = 16;
+const CONSECUTIVE_BONUS: i32 = 12;
+const WORD_START_BONUS: i32 = 10;
+const PREFIX_BONUS: i32 = 20;
+const GAP_PENALTY: i32 = 1;
+const NAME_BONUS: i32 = 50; // Prefer hits in the display name over hits in the path
+
+/// Score `text` against `query` as a case-insensitive subsequence match.
+/// Returns None if some query character is missing, higher is better otherwise.
+pub fn score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last_match: Option<usize> = None;
+
+    for q in query.chars().flat_map(char::to_lowercase) {
+        if q.is_whitespace() {
+            continue;
+        }
+        let found = pos + text[pos..].iter().position(|&c| c == q)?;
+
+        score += MATCH_SCORE;
+        match last_match {
+            Some(last) if last + 1 == found => score += CONSECUTIVE_BONUS,
+            Some(last) => score -= (found - last - 1) as i32 * GAP_PENALTY,
+            None if found == 0 => score += PREFIX_BONUS,
+            None => score -= found as i32 * GAP_PENALTY,
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+
+        last_match = Some(found);
+        pos = found + 1;
+    }
+
+    Some(score)
+}
+
+/// Score a browser entry by its name and executable path
+pub fn score_browser(query: &str, name: &str, path: &str) -> Option<i32> {
+    let name_score = score(query, name).map(|s| s + NAME_BONUS);
+    name_score.max(score(query, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subsequence_is_required() {
+        assert!(score("ffx", "Firefox").is_some());
+        assert!(score("chr", "Firefox").is_none());
+        assert_eq!(score("", "Firefox"), Some(0));
+    }
+
+    #[test]
+    fn prefix_and_word_starts_rank_higher() {
+        let dev = score("fde", "Firefox Developer Edition").unwrap();
+        let scattered = score("fde", "Safari Downloader Extra").unwrap();
+        assert!(dev > scattered);
+        assert!(score("chrome", "Chrome").unwrap() > score("chrome", "Google Chrome").unwrap());
+    }
+
+    #[test]
+    fn name_beats_path() {
+        let by_name = score_browser("edge", "Edge", "C:\\msedge.exe").unwrap();
+        let by_path = score_browser("edge", "Work", "C:\\msedge.exe").unwrap();
+        assert!(by_name > by_path);
+        assert!(score_browser("edge", "Work", "C:\\chrome.exe").is_none());
+    }
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Error, ErrorKind, Write};
+use std::path::Path;
+use std::result::Result;
+
+// Entries allowed past the limit, in percent of it, so the file is not rewritten on every launch
+const TRIM_MARGIN_PERCENT: usize = 10;
+
+/// How the browser for a URL was chosen
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Routing {
+    /// Picked by hand in the window or from the command line
+    Manual,
+    /// Chosen without user interaction
+    Automatic,
+}
+
+/// One handled URL, stored as a line of JSON in `history.jsonl`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub timestamp: usize, // Unix milliseconds
+    pub url: String,
+    pub browser: String,
+    pub routing: Routing,
+}
+
+impl HistoryEntry {
+    pub fn new(url: &str, browser: &str, routing: Routing) -> Self {
+        Self {
+            timestamp: crate::utils::get_unix_msec(),
+            url: url.to_string(),
+            browser: browser.to_string(),
+            routing,
+        }
+    }
+
+    /// Case-insensitive substring match on URL and browser name
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        self.url.to_lowercase().contains(&query) || self.browser.to_lowercase().contains(&query)
+    }
+
+    /// Timestamp as `YYYY-MM-DD HH:MM` in UTC
+    pub fn time_string(&self) -> String {
+        let secs = self.timestamp / 1000;
+        let (days, rem) = (secs / 86400, secs % 86400);
+
+        // Civil date from days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+        let z = days as i64 + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + (month <= 2) as i64;
+
+        format!(
+            "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+            rem / 3600,
+            rem % 3600 / 60
+        )
+    }
+}
+
+/// Load every entry, oldest first, skipping lines that don't parse
+pub fn load() -> Vec<HistoryEntry> {
+    let path = crate::utils::history_path();
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => {
+            if e.kind() != ErrorKind::NotFound {
+                log::error!("Failed to read history {}: {e}", path.display());
+            }
+            return Vec::new();
+        }
+    };
+
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::warn!("Skipping unreadable history line: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Append an entry, dropping the oldest ones once there are more than `limit` (0 keeps everything)
+pub fn append(entry: &HistoryEntry, limit: usize) -> Result<(), Error> {
+    append_to(&crate::utils::history_path(), entry, limit)
+}
+
+fn append_to(path: &Path, entry: &HistoryEntry, limit: usize) -> Result<(), Error> {
+    let line = serde_json::to_string(entry).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Failed to serialize history entry: {}", e),
+        )
+    })?;
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{line}")?;
+    let size = file.metadata()?.len() as usize;
+    drop(file);
+
+    if limit == 0 {
+        return Ok(());
+    }
+    let max = limit + limit * TRIM_MARGIN_PERCENT / 100;
+    // Estimate the entry count from the size of this one, only read the file when it may be over
+    if size / (line.len() + 1) <= max {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(path)?;
+    let lines: Vec<&str> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    if lines.len() > max {
+        let mut kept = lines[lines.len() - limit..].join("\n");
+        kept.push('\n');
+        fs::write(path, kept)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trimmed_once_past_the_margin() {
+        let path =
+            std::env::temp_dir().join(format!("url_proxy_history_{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let count = |path: &Path| fs::read_to_string(path).unwrap().lines().count();
+
+        // A limit of 10 leaves room for one more entry
+        for i in 0..11 {
+            let entry = HistoryEntry::new(
+                &format!("https://example.com/{i:02}"),
+                "Firefox",
+                Routing::Manual,
+            );
+            append_to(&path, &entry, 10).unwrap();
+        }
+        assert_eq!(count(&path), 11);
+
+        let entry = HistoryEntry::new("https://example.com/11", "Firefox", Routing::Manual);
+        append_to(&path, &entry, 10).unwrap();
+        assert_eq!(count(&path), 10);
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.lines().next().unwrap().contains("/02"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn time_string_is_utc_date() {
+        let mut entry = HistoryEntry::new("https://example.com", "Firefox", Routing::Manual);
+        entry.timestamp = 1_709_210_096_000; // 2024-02-29 12:34:56 UTC
+        assert_eq!(entry.time_string(), "2024-02-29 12:34");
+        entry.timestamp = 0;
+        assert_eq!(entry.time_string(), "1970-01-01 00:00");
+    }
+
+    #[test]
+    fn matches_url_and_browser() {
+        let entry = HistoryEntry::new("https://Example.com/page", "Firefox", Routing::Automatic);
+        assert!(entry.matches("example"));
+        assert!(entry.matches("FIREfox"));
+        assert!(!entry.matches("chrome"));
+    }
+}
@@ -0,0 +1,602 @@
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use std::io::{Error, ErrorKind};
+
+/// Schemes allowed out of the box, `javascript:`, `data:` and `vbscript:` are left out on purpose
+pub const DEFAULT_ALLOWED_SCHEMES: &[&str] =
+    &["http", "https", "mailto", "tel", "ftp", "magnet", "file"];
+
+// Extensions Windows runs instead of displaying
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "exe",
+    "com",
+    "bat",
+    "cmd",
+    "scr",
+    "pif",
+    "msi",
+    "msp",
+    "cpl",
+    "hta",
+    "lnk",
+    "reg",
+    "ps1",
+    "psm1",
+    "vb",
+    "vbs",
+    "vbe",
+    "js",
+    "jse",
+    "wsf",
+    "wsh",
+    "jar",
+    "url",
+    "appref-ms",
+    "application",
+    "msc",
+    "chm",
+    "scf",
+    "settingcontent-ms",
+    "library-ms",
+];
+
+/// Which URLs may be handed to a program
+#[derive(Clone, Debug)]
+pub struct Policy {
+    pub schemes: Vec<String>,   // Allowed schemes in lowercase
+    pub executable_files: bool, // Allow `file:` URLs of programs and scripts
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            schemes: DEFAULT_ALLOWED_SCHEMES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            executable_files: false,
+        }
+    }
+}
+
+// Characters escaped in the path of a file URL, `/` and `:` stay readable
+const PATH_ESCAPE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Turn a Windows path (`C:\page.html`, `\\server\share\page.html`) into a `file:` URL,
+/// anything else is returned unchanged
+pub fn normalize(arg: &str) -> String {
+    let trimmed = arg.trim().trim_matches('"');
+    // Long path forms `\\?\C:\...` and `\\?\UNC\server\...` name the same files
+    let long_unc = trimmed
+        .get(..8)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("\\\\?\\UNC\\"))
+        .map(|_| format!("\\\\{}", &trimmed[8..]));
+    let trimmed = match long_unc {
+        Some(ref unc) => unc.as_str(),
+        None => trimmed.strip_prefix("\\\\?\\").unwrap_or(trimmed),
+    };
+    let path = trimmed.replace('\\', "/");
+    let bytes = path.as_bytes();
+
+    if trimmed.starts_with("\\\\") {
+        let unc = path.trim_start_matches('/');
+        if !unc.is_empty() {
+            return format!("file://{}", utf8_percent_encode(unc, PATH_ESCAPE));
+        }
+    }
+    let is_drive = bytes.len() >= 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes.len() == 2 || bytes[2] == b'/');
+    if is_drive {
+        return format!("file:///{}", utf8_percent_encode(&path, PATH_ESCAPE));
+    }
+    arg.to_string()
+}
+
+/// Check a URL before it goes on a command line and return it in canonical form.
+/// Refuses anything that could be read as an option, control characters and what `policy` blocks.
+pub fn canonicalize(url: &str, policy: &Policy) -> Result<String, Error> {
+    let invalid = |message: String| Error::new(ErrorKind::InvalidInput, message);
+    let url = normalize(url);
+    let url = url.trim();
+    if url.is_empty() {
+        return Err(invalid("URL is empty".to_string()));
+    }
+    if url.starts_with('-') {
+        return Err(invalid(format!("{url} would be read as an option")));
+    }
+    // The parser drops tabs and newlines silently, catch them first
+    if url.chars().any(char::is_control) {
+        return Err(invalid("URL contains control characters".to_string()));
+    }
+
+    let parsed = match url::Url::parse(url) {
+        // Bare hosts with a port like `localhost:3000` read as a scheme, unless it is an allowed one
+        Ok(parsed)
+            if !policy.schemes.iter().any(|s| s == parsed.scheme())
+                && is_host_and_port(url, parsed.scheme()) =>
+        {
+            url::Url::parse(&format!("https://{url}"))
+                .map_err(|e| invalid(format!("Invalid URL {url}: {e}")))?
+        }
+        Ok(parsed) => parsed,
+        // Bare hosts like `example.com/page`
+        Err(url::ParseError::RelativeUrlWithoutBase) => url::Url::parse(&format!("https://{url}"))
+            .map_err(|e| invalid(format!("Invalid URL {url}: {e}")))?,
+        Err(e) => return Err(invalid(format!("Invalid URL {url}: {e}"))),
+    };
+    if !policy.schemes.iter().any(|s| s == parsed.scheme()) {
+        return Err(invalid(format!(
+            "{}: URLs are not allowed",
+            parsed.scheme()
+        )));
+    }
+    if !policy.executable_files && is_executable_file(parsed.as_str()) {
+        return Err(invalid(format!("{url} is a program, not a page")));
+    }
+    Ok(parsed.to_string())
+}
+
+/// Whether the `scheme` parsed from `url` is really a host followed by a port, like
+/// `example.com:8080/x`. Schemes written as `name:/...` are left alone.
+fn is_host_and_port(url: &str, scheme: &str) -> bool {
+    let rest = url.get(scheme.len() + 1..).unwrap_or_default();
+    let port = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let is_port = !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit());
+    is_port || (scheme.contains('.') && !rest.starts_with('/'))
+}
+
+/// Whether a `file:` URL points at something Windows would run
+fn is_executable_file(url: &str) -> bool {
+    let Some(path) = file_path(url) else {
+        return false;
+    };
+    let name = path.rsplit('\\').next().unwrap_or_default();
+    // Windows opens `x.exe` for `x.exe.`, `x.exe ` and the data stream `x.exe::$DATA`
+    let name = name
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(['.', ' ']);
+    let extension = name.rsplit_once('.').map(|(_, e)| e.to_lowercase());
+    extension.is_some_and(|e| EXECUTABLE_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// Windows path of a `file:` URL, None for other URLs
+pub fn file_path(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url.trim()).ok()?;
+    if parsed.scheme() != "file" {
+        return None;
+    }
+    let path = percent_decode_str(parsed.path()).decode_utf8().ok()?;
+    let path = match parsed.host_str() {
+        Some(host) if !host.is_empty() => format!("//{host}{path}"),
+        _ => path.trim_start_matches('/').to_string(),
+    };
+    Some(path.replace('/', "\\"))
+}
+
+/// Scheme of a URL in lowercase, None for plain paths like `C:\page.html`
+pub fn scheme(url: &str) -> Option<String> {
+    let (scheme, _) = url.trim().split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    // A single letter before the colon is a drive, not a scheme
+    (valid && scheme.len() > 1).then(|| scheme.to_ascii_lowercase())
+}
+
+// Second-level labels that are sold as suffixes under country codes, like `co.uk` or `com.au`.
+// A short stand-in for the public suffix list, which isn't shipped.
+const SECOND_LEVEL_SUFFIXES: &[&str] = &[
+    "ac", "co", "com", "edu", "gob", "go", "gov", "ltd", "me", "mil", "ne", "net", "nic", "or",
+    "org", "plc", "sch",
+];
+
+/// The part of a host that is registered with a registrar, like `example.co.uk` for
+/// `www.example.co.uk`. IP addresses and single labels are returned whole.
+pub fn registrable_domain(host: &str) -> &str {
+    let host = host.trim_end_matches('.');
+    if host.parse::<std::net::IpAddr>().is_ok() || host.starts_with('[') {
+        return host;
+    }
+    let labels: Vec<&str> = host.split('.').collect();
+    let n = labels.len();
+    let country_suffix = n >= 3
+        && labels[n - 1].len() == 2
+        && SECOND_LEVEL_SUFFIXES.contains(&labels[n - 2].to_ascii_lowercase().as_str());
+    let keep = if country_suffix { 3 } else { 2 };
+    if n <= keep {
+        return host;
+    }
+    let start: usize = labels[..n - keep].iter().map(|l| l.len() + 1).sum();
+    &host[start..]
+}
+
+/// A URL split up for display, the pieces joined give the canonical URL back
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UrlParts {
+    pub prefix: String,    // Scheme, credentials and `//`
+    pub subdomain: String, // Host labels in front of the registrable domain, with the dot
+    pub domain: String,    // Registrable domain
+    pub rest: String,      // Port, path, query and fragment
+    pub query: Vec<(String, String)>,
+}
+
+/// Split a URL with a host for display, None for URLs without one
+pub fn split_for_display(url: &str) -> Option<UrlParts> {
+    use url::Position;
+    let parsed = url::Url::parse(url.trim()).ok()?;
+    let host = parsed.host_str().filter(|h| !h.is_empty())?;
+    // Measured on the host without its trailing dot, which stays with the domain
+    let trimmed = host.trim_end_matches('.');
+    let (subdomain, domain) = host.split_at(trimmed.len() - registrable_domain(trimmed).len());
+    Some(UrlParts {
+        prefix: parsed[..Position::BeforeHost].to_string(),
+        subdomain: subdomain.to_string(),
+        domain: domain.to_string(),
+        rest: parsed[Position::AfterHost..].to_string(),
+        query: parsed.query_pairs().into_owned().collect(),
+    })
+}
+
+/// Host of a URL as sent and as a person reads it, with what looks suspicious about it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostInfo {
+    pub raw: String,     // ASCII form, punycode for international names
+    pub unicode: String, // Decoded form
+    pub warnings: Vec<String>,
+}
+
+/// Writing systems told apart when looking for mixed-script labels
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Cjk,
+    Other,
+}
+
+impl Script {
+    /// Script of a letter, None for digits, hyphens and other characters shared by all scripts
+    fn of(c: char) -> Option<Self> {
+        let script = match c as u32 {
+            0x30..=0x39 | 0x2d | 0x5f => return None,
+            0x61..=0x7a | 0x41..=0x5a | 0xc0..=0x24f | 0x1e00..=0x1eff => Script::Latin,
+            0x370..=0x3ff | 0x1f00..=0x1fff => Script::Greek,
+            0x400..=0x52f | 0x1c80..=0x1c8f | 0x2de0..=0x2dff | 0xa640..=0xa69f => Script::Cyrillic,
+            0x530..=0x58f => Script::Armenian,
+            0x590..=0x5ff => Script::Hebrew,
+            0x600..=0x6ff | 0x750..=0x77f => Script::Arabic,
+            0x3040..=0x30ff | 0x3400..=0x4dbf | 0x4e00..=0x9fff | 0xac00..=0xd7af => Script::Cjk,
+            _ => Script::Other,
+        };
+        Some(script)
+    }
+}
+
+/// Latin letter a non-Latin character is easily mistaken for
+fn latin_lookalike(c: char) -> Option<char> {
+    let latin = match c {
+        'а' | 'α' => 'a',
+        'Ь' | 'ь' | 'β' => 'b',
+        'с' | 'ϲ' => 'c',
+        'ԁ' => 'd',
+        'е' | 'ε' => 'e',
+        'ɡ' => 'g',
+        'һ' => 'h',
+        'і' | 'ι' | 'ı' => 'i',
+        'ј' | 'ϳ' => 'j',
+        'κ' | 'к' => 'k',
+        'ӏ' | 'ⅼ' => 'l',
+        'м' => 'm',
+        'п' | 'η' => 'n',
+        'о' | 'ο' | 'օ' => 'o',
+        'р' | 'ρ' => 'p',
+        'ԛ' => 'q',
+        'г' => 'r',
+        'ѕ' => 's',
+        'т' | 'τ' => 't',
+        'υ' | 'ս' => 'u',
+        'ν' | 'ѵ' => 'v',
+        'ԝ' | 'ω' | 'ա' => 'w',
+        'х' | 'χ' => 'x',
+        'у' | 'γ' => 'y',
+        'ᴢ' => 'z',
+        _ => return None,
+    };
+    Some(latin)
+}
+
+/// Decode the host of a URL and flag mixed-script and lookalike labels
+pub fn inspect_host(url: &str) -> Option<HostInfo> {
+    let parsed = url::Url::parse(url.trim()).ok()?;
+    let raw = parsed.host_str()?.to_lowercase();
+    let (unicode, result) = idna::domain_to_unicode(&raw);
+    let mut warnings = Vec::new();
+    if result.is_err() {
+        warnings.push("Host has invalid punycode".to_string());
+    }
+
+    for label in unicode.split('.') {
+        let mut scripts: Vec<Script> = Vec::new();
+        for script in label.chars().filter_map(Script::of) {
+            if !scripts.contains(&script) {
+                scripts.push(script);
+            }
+        }
+        // Japanese and Chinese names mix in Latin letters legitimately
+        let mixed = scripts.len() > 1
+            && !(scripts.len() == 2
+                && scripts.contains(&Script::Latin)
+                && scripts.contains(&Script::Cjk));
+        if mixed {
+            let names: Vec<String> = scripts.iter().map(|s| format!("{s:?}")).collect();
+            warnings.push(format!("{label} mixes {} letters", names.join(" and ")));
+        }
+    }
+
+    if !unicode.is_ascii() {
+        let lookalike: Option<String> = unicode
+            .chars()
+            .map(|c| match c.is_ascii() {
+                true => Some(c),
+                false => latin_lookalike(c),
+            })
+            .collect();
+        if let Some(lookalike) = lookalike {
+            warnings.push(format!("{unicode} looks like {lookalike}"));
+        }
+    }
+
+    Some(HostInfo {
+        raw,
+        unicode,
+        warnings,
+    })
+}
+
+/// Host of a URL in lowercase, None for URLs without one (mailto:, file paths, garbage)
+pub fn host(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url.trim()).ok()?;
+    parsed.host_str().map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheme_is_lowercase_and_skips_drives() {
+        assert_eq!(scheme("HTTPS://example.com").as_deref(), Some("https"));
+        assert_eq!(
+            scheme("mailto:someone@example.com").as_deref(),
+            Some("mailto")
+        );
+        assert_eq!(scheme("vscode://file/C:/x").as_deref(), Some("vscode"));
+        assert_eq!(scheme("C:\\pages\\index.html"), None);
+        assert_eq!(scheme("example.com/page"), None);
+    }
+
+    #[test]
+    fn drive_paths_become_file_urls() {
+        assert_eq!(normalize("C:\\foo\\page.html"), "file:///C:/foo/page.html");
+        assert_eq!(
+            normalize("\"D:\\My Pages\\#1 ünï.htm\""),
+            "file:///D:/My%20Pages/%231%20%C3%BCn%C3%AF.htm"
+        );
+        assert_eq!(normalize("c:/foo/page.html"), "file:///c:/foo/page.html");
+        assert_eq!(normalize("\\\\?\\C:\\a\\b.html"), "file:///C:/a/b.html");
+    }
+
+    #[test]
+    fn unc_paths_keep_the_server() {
+        assert_eq!(
+            normalize("\\\\server\\share\\a b.html"),
+            "file://server/share/a%20b.html"
+        );
+        assert_eq!(
+            normalize("\\\\?\\UNC\\server\\share\\a b.html"),
+            "file://server/share/a%20b.html"
+        );
+    }
+
+    #[test]
+    fn urls_are_left_alone() {
+        for url in [
+            "https://example.com/a b",
+            "mailto:x@example.com",
+            "page.html",
+        ] {
+            assert_eq!(normalize(url), url);
+        }
+    }
+
+    #[test]
+    fn canonicalize_refuses_options_and_controls() {
+        for url in [
+            "--remote-debugging-port=9222",
+            " -incognito",
+            "https://example.com/\nx",
+            "https://exa\u{7}mple.com",
+            "javascript:alert(1)",
+            "JavaScript:alert(1)",
+            "data:text/html,<b>x</b>",
+            "",
+        ] {
+            let error = canonicalize(url, &Policy::default()).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput, "{url:?}");
+        }
+    }
+
+    #[test]
+    fn policy_blocks_schemes_and_programs() {
+        let policy = Policy::default();
+        for url in [
+            "vbscript:msgbox(1)",
+            "file:///C:/Users/me/Downloads/invoice.pdf.EXE",
+            "C:\\tools\\setup.msi",
+            "\\\\server\\share\\run.bat",
+            "slack://open",
+            "file:///C:/Users/me/x.exe.",
+            "file:///C:/Users/me/x.exe%20",
+            "file:///C:/Users/me/x.exe%20.%20",
+            "file:///C:/Users/me/x.exe::$DATA",
+            "file:///C:/Users/me/x.url",
+            "file:///C:/Users/me/x.appref-ms",
+            "file:///C:/Users/me/x.application",
+            "file:///C:/Users/me/x.msc",
+            "file:///C:/Users/me/x.chm",
+            "file:///C:/Users/me/x.scf",
+            "file:///C:/Users/me/x.settingcontent-ms",
+            "file:///C:/Users/me/x.library-ms",
+            "file:///C:/Users/me/x.psm1",
+            "file:///C:/Users/me/x.vb",
+        ] {
+            assert!(canonicalize(url, &policy).is_err(), "{url:?}");
+        }
+        assert!(canonicalize("file:///C:/pages/index.html", &policy).is_ok());
+
+        let custom = Policy {
+            schemes: vec!["slack".to_string(), "file".to_string()],
+            executable_files: true,
+        };
+        assert!(canonicalize("slack://open", &custom).is_ok());
+        assert!(canonicalize("C:\\tools\\setup.msi", &custom).is_ok());
+        assert!(canonicalize("https://example.com", &custom).is_err());
+    }
+
+    #[test]
+    fn registrable_domain_skips_subdomains() {
+        assert_eq!(registrable_domain("www.example.com"), "example.com");
+        assert_eq!(registrable_domain("a.b.example.co.uk"), "example.co.uk");
+        assert_eq!(registrable_domain("example.com.au"), "example.com.au");
+        assert_eq!(registrable_domain("login.example.io"), "example.io");
+        assert_eq!(registrable_domain("localhost"), "localhost");
+        assert_eq!(registrable_domain("192.168.0.1"), "192.168.0.1");
+    }
+
+    #[test]
+    fn split_for_display_covers_the_url() {
+        let url = "https://user@login.example.co.uk:8443/a/b?x=1&y=h%C3%A9#top";
+        let parts = split_for_display(url).unwrap();
+        assert_eq!(parts.prefix, "https://user@");
+        assert_eq!(parts.subdomain, "login.");
+        assert_eq!(parts.domain, "example.co.uk");
+        assert_eq!(parts.rest, ":8443/a/b?x=1&y=h%C3%A9#top");
+        assert_eq!(
+            parts.query,
+            [
+                ("x".to_string(), "1".to_string()),
+                ("y".to_string(), "hé".to_string())
+            ]
+        );
+        let joined = parts.prefix + &parts.subdomain + &parts.domain + &parts.rest;
+        assert_eq!(joined, url);
+
+        // A fully qualified host keeps its dot on the domain
+        let url = "https://paypal.com.evil.example./a";
+        let parts = split_for_display(url).unwrap();
+        assert_eq!(parts.subdomain, "paypal.com.");
+        assert_eq!(parts.domain, "evil.example.");
+        let joined = parts.prefix + &parts.subdomain + &parts.domain + &parts.rest;
+        assert_eq!(joined, url);
+        let parts = split_for_display("https://www.example.com./a").unwrap();
+        assert_eq!(
+            (parts.subdomain.as_str(), parts.domain.as_str()),
+            ("www.", "example.com.")
+        );
+        assert!(split_for_display("mailto:x@example.com").is_none());
+    }
+
+    #[test]
+    fn punycode_is_decoded() {
+        let info = inspect_host("https://xn--mnchen-3ya.de/").unwrap();
+        assert_eq!(info.raw, "xn--mnchen-3ya.de");
+        assert_eq!(info.unicode, "münchen.de");
+        assert!(info.warnings.is_empty());
+
+        let info = inspect_host("https://例え.テスト/").unwrap();
+        assert_eq!(info.unicode, "例え.テスト");
+        assert!(info.warnings.is_empty());
+        assert!(inspect_host("mailto:x@example.com").is_none());
+    }
+
+    #[test]
+    fn lookalikes_are_flagged() {
+        // Cyrillic а in front of Latin pple
+        let info = inspect_host("https://\u{430}pple.com/login").unwrap();
+        assert!(info.raw.starts_with("xn--"));
+        assert_eq!(info.warnings.len(), 2, "{:?}", info.warnings);
+        assert!(info.warnings[0].contains("mixes Cyrillic and Latin"));
+        assert!(info.warnings[1].ends_with("looks like apple.com"));
+
+        // Entirely Cyrillic, not mixed but still a lookalike
+        let info = inspect_host("https://\u{440}\u{430}\u{443}\u{440}\u{430}\u{04cf}.com").unwrap();
+        assert_eq!(info.warnings.len(), 1, "{:?}", info.warnings);
+        assert!(info.warnings[0].ends_with("looks like paypal.com"));
+
+        assert!(inspect_host("https://example.com")
+            .unwrap()
+            .warnings
+            .is_empty());
+    }
+
+    #[test]
+    fn canonicalize_normalizes() {
+        assert_eq!(
+            canonicalize(" HTTPS://Example.COM/a b ", &Policy::default()).unwrap(),
+            "https://example.com/a%20b"
+        );
+        assert_eq!(
+            canonicalize("example.com/page", &Policy::default()).unwrap(),
+            "https://example.com/page"
+        );
+        assert_eq!(
+            canonicalize("localhost:3000", &Policy::default()).unwrap(),
+            "https://localhost:3000/"
+        );
+        assert_eq!(
+            canonicalize("example.com:8080/x", &Policy::default()).unwrap(),
+            "https://example.com:8080/x"
+        );
+        assert_eq!(
+            canonicalize("tel:5551234", &Policy::default()).unwrap(),
+            "tel:5551234"
+        );
+        assert_eq!(
+            canonicalize("C:\\pages\\index.html", &Policy::default()).unwrap(),
+            "file:///C:/pages/index.html"
+        );
+        assert_eq!(
+            canonicalize("mailto:someone@example.com", &Policy::default()).unwrap(),
+            "mailto:someone@example.com"
+        );
+    }
+
+    #[test]
+    fn file_path_reverses_normalize() {
+        for path in ["C:\\My Pages\\#1 ünï.htm", "\\\\server\\share\\a b.html"] {
+            assert_eq!(file_path(&normalize(path)).as_deref(), Some(path));
+        }
+        assert_eq!(file_path("https://example.com/a"), None);
+    }
+}
@@ -1,8 +1,19 @@
 #![windows_subsystem = "windows"]
 
+mod blocklist;
 mod cache;
+mod cli;
+mod fuzzy;
+mod history;
+mod link;
 mod mainwindow;
+mod placement;
+mod rewrite;
 mod settings;
+mod source;
+mod state;
+mod theme;
+mod usage;
 pub mod utils;
 
 use eframe::egui;
@@ -22,27 +33,55 @@ lazy_static::lazy_static! {
     };
 }
 
+/// What the process was started to do
+enum Invocation {
+    /// Show the picker for a URL
+    Open(String),
+    /// `url_proxy history ...`, handled on the command line
+    History(Vec<String>),
+}
+
 fn main() -> Result<(), Error> {
     // Parse command line arguments
-    let url = parse_command_line();
+    let invocation = parse_command_line();
 
     // Initialize logger
     setup_logger()?;
+
+    let url = match invocation {
+        Invocation::Open(url) => link::normalize(&url),
+        Invocation::History(args) => return cli::history(&args, &load_settings()?),
+    };
     info!("Opening URL: {url}");
 
     // Load settings
     let settings = load_settings()?;
 
+    // Skip the picker when a route matches the application that opened the link
+    if !settings.routes.is_empty() {
+        if let Some(app) = source::source_app() {
+            info!("Link opened from {app}");
+            if source::open_routed(&url, &settings, &app) {
+                return Ok(());
+            }
+        }
+    }
+
+    // Load window geometry from the last run
+    let window_state = state::WindowState::load();
+
     // Calculate window dimensions
-    let (inner_width, inner_height, pos_x, pos_y) = calculate_window_dimensions(&settings);
+    let (inner_width, inner_height, pos_x, pos_y) =
+        calculate_window_dimensions(&settings, &window_state);
 
     // Create window options
     let options = create_window_options(inner_width, inner_height, pos_x, pos_y)?;
 
     // Store settings values we need for the app (move Copy types directly)
-    let settings_browsers = settings.browsers.clone();
+    let settings_browsers = settings.handlers_for(&url).to_vec();
+    let settings_groups = settings.groups.clone();
     let cache_exp_days = settings.cache_expire_days;
-    let settings_cols = settings.cols;
+    let cache_backend = settings.cache_backend;
 
     // Run the application
     eframe::run_native(
@@ -53,16 +92,24 @@ fn main() -> Result<(), Error> {
             setup_fonts(cc);
 
             // Setup browser icons
-            let browsers = setup_browser_icons(cc, settings_browsers, cache_exp_days);
+            let browsers = setup_browser_icons(
+                cc,
+                settings_browsers,
+                &settings_groups,
+                cache_exp_days,
+                cache_backend,
+            );
 
-            // Set dark theme
-            cc.egui_ctx.set_theme(egui::Theme::Dark);
+            // Apply theme and custom colors
+            theme::apply(&cc.egui_ctx, settings.theme, &settings.palette);
 
             // Create and return the main window
             Ok(Box::new(mainwindow::MainWindow::new(
                 url,
                 browsers,
-                settings_cols,
+                &settings,
+                usage::Usage::load(),
+                window_state,
             )))
         }),
     )
@@ -71,9 +118,13 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
-/// Parse command line arguments and return the URL
-fn parse_command_line() -> String {
-    std::env::args().nth(1).unwrap_or_default()
+/// Parse command line arguments into what to do
+fn parse_command_line() -> Invocation {
+    let mut args = std::env::args().skip(1);
+    match args.next() {
+        Some(command) if command == "history" => Invocation::History(args.collect()),
+        url => Invocation::Open(url.unwrap_or_default()),
+    }
 }
 
 /// Setup the logger with rolling file appender
@@ -127,17 +178,24 @@ fn load_settings() -> Result<settings::Settings, Error> {
     Ok(settings)
 }
 
-/// Calculate window dimensions based on settings and screen size
-fn calculate_window_dimensions(settings: &settings::Settings) -> (f32, f32, f32, f32) {
-    let (sc_width, sc_height) = utils::get_screen_size();
-
+/// Calculate window dimensions based on settings and monitor layout
+fn calculate_window_dimensions(
+    settings: &settings::Settings,
+    window_state: &state::WindowState,
+) -> (f32, f32, f32, f32) {
     // Calculate window size
-    let inner_width = settings.cols as f32 * (mainwindow::CARD_WIDTH + 20.0);
-    let inner_height = settings.rows as f32 * (mainwindow::CARD_HEIGHT + 15.0) + 40.0;
-
-    // Calculate window position (centered)
-    let pos_x = (sc_width as f32 - inner_width) / 2.0;
-    let pos_y = (sc_height as f32 - inner_height) / 2.0;
+    let layout =
+        mainwindow::CardLayout::new(settings.layout, settings.card_width, settings.card_height);
+    let (inner_width, inner_height) =
+        window_state.size_for(layout.window_size(settings.cols, settings.rows));
+
+    // Calculate window position on the chosen monitor
+    let (pos_x, pos_y) = placement::window_position(
+        settings.placement,
+        (inner_width, inner_height),
+        window_state.position,
+        window_state.scale,
+    );
 
     (inner_width, inner_height, pos_x, pos_y)
 }
@@ -167,7 +225,7 @@ fn create_window_options(
             inner_size: Some(egui::vec2(inner_width, inner_height)),
             position: Some(egui::pos2(pos_x, pos_y)),
             window_level: Some(egui::WindowLevel::AlwaysOnTop),
-            resizable: Some(false),
+            resizable: Some(true),
             decorations: Some(false),
             icon: Some(std::sync::Arc::new(icon_data)),
             ..Default::default()
@@ -216,7 +274,9 @@ fn setup_fonts(cc: &eframe::CreationContext) {
 fn setup_browser_icons(
     cc: &eframe::CreationContext,
     browsers_config: Vec<settings::BrowserInfo>,
+    groups: &[settings::BrowserGroup],
     cache_expire_days: usize,
+    cache_backend: cache::CacheBackend,
 ) -> Vec<mainwindow::BrowserShow> {
     // Create texture manager and default icon
     let manager = cc.egui_ctx.tex_manager();
@@ -227,7 +287,7 @@ fn setup_browser_icons(
     );
 
     // Initialize cache manager
-    let mut cache = cache::IconCacheManager::new(cache_expire_days);
+    let mut cache = cache::IconCacheManager::new(cache_expire_days, cache_backend);
     let mut browsers = Vec::new();
 
     // Create browser objects with icons
@@ -239,12 +299,14 @@ fn setup_browser_icons(
                 browser.name,
                 browser.path,
                 browser.cmd,
+                browser.launch,
                 img,
             ),
             None => mainwindow::BrowserShow::new_without_icon(
                 browser.name,
                 browser.path,
                 browser.cmd,
+                browser.launch,
                 default_icon,
                 PLACEHOLDER.1,
             ),
@@ -252,5 +314,12 @@ fn setup_browser_icons(
         browsers.push(browser_show);
     }
 
+    // Groups come after the browsers and borrow the icon of their first member
+    let group_cards: Vec<_> = groups
+        .iter()
+        .map(|group| mainwindow::BrowserShow::group(group, &browsers, default_icon, PLACEHOLDER.1))
+        .collect();
+    browsers.extend(group_cards);
+
     browsers
 }
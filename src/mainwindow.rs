@@ -1,13 +1,111 @@
 use eframe::egui;
+use serde::{Deserialize, Serialize};
 
-pub const CARD_WIDTH: f32 = 60.0;
-pub const CARD_HEIGHT: f32 = 90.0;
+const LIST_WIDTH: f32 = 240.0; // Width of a row in the list layout
+const LIST_ICON_SIZE: f32 = 24.0;
+const TYPE_AHEAD_TIMEOUT: f64 = 1.0; // Seconds before typed letters start a new search
+
+/// How the browser cards are arranged
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    /// `cols` cards per row, icon above the name
+    #[default]
+    Grid,
+    /// Grid with icons twice the card width
+    LargeIcons,
+    /// One row per browser, small icon and the full name
+    List,
+    /// All cards in a single scrolling row
+    Strip,
+}
+
+/// What happens when the picker loses focus, e.g. after a click outside it
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusLoss {
+    /// Stay open until a browser is picked or the window is closed
+    #[default]
+    Stay,
+    /// Close without opening the URL
+    Close,
+    /// Run the default action, like the end of the countdown
+    DefaultAction,
+}
+
+/// Card sizes for a layout, derived from the configured card width and height
+#[derive(Clone, Copy)]
+pub struct CardLayout {
+    pub mode: Layout,
+    pub width: f32,  // Card width, also the icon size in the grid
+    pub height: f32, // Card height including the name label
+}
+
+impl CardLayout {
+    pub fn new(mode: Layout, width: usize, height: usize) -> Self {
+        let width = width.max(16) as f32;
+        Self {
+            mode,
+            width,
+            height: (height as f32).max(width),
+        }
+    }
+
+    /// Size of the square icon on a card
+    pub fn icon_size(&self) -> f32 {
+        match self.mode {
+            Layout::Grid | Layout::Strip => self.width,
+            Layout::LargeIcons => self.width * 2.0,
+            Layout::List => LIST_ICON_SIZE,
+        }
+    }
+
+    /// Size of a whole card
+    pub fn card_size(&self) -> egui::Vec2 {
+        match self.mode {
+            Layout::Grid | Layout::Strip => egui::vec2(self.width, self.height),
+            Layout::LargeIcons => egui::vec2(self.width * 2.0, self.height + self.width),
+            Layout::List => egui::vec2(LIST_WIDTH, LIST_ICON_SIZE + 8.0),
+        }
+    }
+
+    /// Cards per row that fit a window `width` points wide, for `count` cards
+    pub fn columns(&self, width: f32, count: usize) -> usize {
+        match self.mode {
+            // Same per-card width as `window_size`, so the default size shows `cols` columns
+            Layout::Grid | Layout::LargeIcons => {
+                ((width / (self.card_size().x + 20.0)).floor() as usize).max(1)
+            }
+            Layout::List => 1,
+            Layout::Strip => count.max(1),
+        }
+    }
+
+    /// Inner window size showing `cols` by `rows` cards
+    pub fn window_size(&self, cols: usize, rows: usize) -> (f32, f32) {
+        let card = self.card_size();
+        let header = 65.0; // Toolbar and search box
+        match self.mode {
+            Layout::Grid | Layout::LargeIcons => (
+                cols as f32 * (card.x + 20.0),
+                rows as f32 * (card.y + 15.0) + header,
+            ),
+            Layout::List => (
+                card.x + 40.0,
+                (cols * rows) as f32 * (card.y + 4.0) + header,
+            ),
+            Layout::Strip => (cols as f32 * (card.x + 20.0), card.y + 30.0 + header),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct BrowserShow {
     pub name: String,
     pub path: String,
     pub cmd: Vec<String>,
+    pub launch: crate::settings::LaunchOptions,
+    pub members: Vec<String>, // Browsers a group card opens, empty for a single browser
     pub icon: egui::TextureId,
     pub width: f32,
     pub height: f32,
@@ -15,8 +113,70 @@ pub struct BrowserShow {
 
 #[derive(Clone)]
 pub struct BrowserExec {
+    pub name: String,
     pub path: String,
     pub cmd: Vec<String>,
+    pub launch: crate::settings::LaunchOptions,
+}
+
+// Process creation flags, see https://learn.microsoft.com/windows/win32/procthread/process-creation-flags
+#[cfg(windows)]
+const DETACHED_PROCESS: u32 = 0x0000_0008;
+#[cfg(windows)]
+const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+impl BrowserExec {
+    pub fn from_info(info: &crate::settings::BrowserInfo) -> Self {
+        Self {
+            name: info.name.clone(),
+            path: info.path.clone(),
+            cmd: info.cmd.clone(),
+            launch: info.launch.clone(),
+        }
+    }
+
+    /// Start the browser with `url` appended to its arguments, returns the URL as passed on
+    pub fn spawn(&self, url: &str, policy: &crate::link::Policy) -> std::io::Result<String> {
+        let url = crate::link::canonicalize(url, policy)?;
+        self.spawn_argument(&url)?;
+        Ok(url)
+    }
+
+    /// Start the program with a single trailing argument, like a file for an editor
+    pub fn spawn_argument(&self, argument: &str) -> std::io::Result<()> {
+        let mut cmds = self.cmd.clone();
+        if self.launch.end_of_options {
+            cmds.push("--".to_string());
+        }
+        cmds.push(argument.to_string());
+        let mut command = std::process::Command::new(self.path.clone());
+        command.args(cmds);
+        if let Some(cwd) = self.launch.cwd.as_deref().filter(|cwd| !cwd.is_empty()) {
+            command.current_dir(cwd);
+        }
+        for (key, value) in &self.launch.env {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            let mut flags = 0;
+            if self.launch.detached {
+                flags |= DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP;
+            }
+            if self.launch.no_window {
+                flags |= CREATE_NO_WINDOW;
+            }
+            command.creation_flags(flags);
+        }
+        command.spawn()?;
+        Ok(())
+    }
 }
 
 impl BrowserShow {
@@ -25,6 +185,7 @@ impl BrowserShow {
         name: String,
         path: String,
         cmd: Vec<String>,
+        launch: crate::settings::LaunchOptions,
         icon: image::RgbaImage,
     ) -> Self {
         let manager = ctx.tex_manager();
@@ -40,6 +201,8 @@ impl BrowserShow {
             name,
             path,
             cmd,
+            launch,
+            members: Vec::new(),
             icon,
             width: size[0] as f32,
             height: size[1] as f32,
@@ -49,6 +212,7 @@ impl BrowserShow {
         name: String,
         path: String,
         cmd: Vec<String>,
+        launch: crate::settings::LaunchOptions,
         default_icon: egui::TextureId,
         size: [usize; 2],
     ) -> Self {
@@ -56,38 +220,955 @@ impl BrowserShow {
             name,
             path,
             cmd,
+            launch,
+            members: Vec::new(),
             icon: default_icon,
             width: size[0] as f32,
             height: size[1] as f32,
         }
     }
-    pub fn into_exec(&self) -> BrowserExec {
+    pub fn group(
+        group: &crate::settings::BrowserGroup,
+        browsers: &[BrowserShow],
+        default_icon: egui::TextureId,
+        size: [usize; 2],
+    ) -> Self {
+        let first = group
+            .members
+            .iter()
+            .find_map(|name| browsers.iter().find(|b| b.name.eq_ignore_ascii_case(name)));
+        let (icon, width, height) = match first {
+            Some(browser) => (browser.icon, browser.width, browser.height),
+            None => (default_icon, size[0] as f32, size[1] as f32),
+        };
+        Self {
+            name: group.name.clone(),
+            path: String::new(),
+            cmd: Vec::new(),
+            launch: Default::default(),
+            members: group.members.clone(),
+            icon,
+            width,
+            height,
+        }
+    }
+    pub fn to_exec(&self) -> BrowserExec {
         BrowserExec {
+            name: self.name.clone(),
             path: self.path.clone(),
             cmd: self.cmd.clone(),
+            launch: self.launch.clone(),
         }
     }
 }
 
+/// Keys the browser grid takes over from egui's own focus handling
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum NavKey {
+    Left,
+    Right,
+    Up,
+    Down,
+    Next,
+    Previous,
+    Activate,
+}
+
 #[derive(Clone)]
 pub struct MainWindow {
     url: String,
     browsers: Vec<BrowserShow>,
-    cols: usize,
+    layout: CardLayout,
+    window_width: f32,          // Inner width of the window, cards reflow to it
+    usage: crate::usage::Usage, // Launch counts for ranking
+    order_by_usage: bool,       // Put the most launched browsers first
+    url_id: egui::Id,           // Id of the URL text field, which keeps its keys while focused
+    editing_url: bool,          // Text field instead of the highlighted URL view
+    focus_url: bool,            // URL text field still has to grab the focus
+    url_edited: bool,           // URL was typed in, rewrite it once editing ends
+    search: String,             // Fuzzy filter typed above the grid
+    search_id: egui::Id,
+    focus_search: bool,       // Search box still has to grab the focus
+    visible: Vec<usize>,      // Indices into `browsers` matching the search, best first
+    selected: Option<usize>,  // Position in `visible` with the keyboard focus ring
+    nav_keys: Vec<NavKey>,    // Navigation keys taken from this frame's input
+    type_ahead: String,       // Letters typed so far to jump to a browser by name
+    type_ahead_time: f64,     // Time of the last typed letter
+    scroll_to_selected: bool, // Selection moved, bring it into view
+    history_limit: usize,     // Entries kept in the history file
+    show_history: bool,       // History panel replaces the grid
+    history: Vec<crate::history::HistoryEntry>,
+    history_search: String,
+    window_state: crate::state::WindowState, // Geometry saved on exit
+    saved_window_state: crate::state::WindowState,
+    timeout: f64,          // Seconds before the default action, 0 waits forever
+    deadline: Option<f64>, // Input time of the default action, None once cancelled
+    pointer_origin: Option<egui::Pos2>, // First pointer position, resting there doesn't cancel
+    default_browser: Option<String>, // Name of the browser the default action opens
+    focus_loss: FocusLoss,
+    was_focused: bool, // Focus has to be gained before losing it counts
+    launch_error: Option<LaunchError>, // Last failed launch, shown until another browser is tried
+    picked: Vec<usize>, // Indices into `browsers` chosen with Ctrl + click, in click order
+    editor: BrowserExec, // Extra target for local files
+    policy: crate::link::Policy, // URLs that may be launched
+    blocklist: crate::blocklist::Blocklist,
+    block_action: crate::blocklist::BlockAction,
+    block_override: Option<String>, // Blocklisted URL the user chose to open anyway
+    rewriter: crate::rewrite::Rewriter,
+    rewrite: Option<crate::rewrite::Rewrite>, // Last rewrite of the URL, kept for undo
+    rewrite_undone: bool,                     // Original URL restored by the user
 }
 
-impl MainWindow {
-    pub fn new(url: String, browsers: Vec<BrowserShow>, cols: usize) -> Self {
+/// A browser that could not be started
+#[derive(Clone)]
+struct LaunchError {
+    browser: String,
+    message: String,
+}
+
+impl LaunchError {
+    fn new(exec: &BrowserExec, error: &std::io::Error) -> Self {
+        let message = match error.kind() {
+            std::io::ErrorKind::NotFound => format!("Executable not found: {}", exec.path),
+            std::io::ErrorKind::PermissionDenied => format!("Permission denied: {}", exec.path),
+            std::io::ErrorKind::InvalidInput => format!("Refused URL: {error}"),
+            _ => format!("Failed to start {}: {error}", exec.path),
+        };
         Self {
+            browser: exec.name.clone(),
+            message,
+        }
+    }
+}
+
+impl MainWindow {
+    pub fn new(
+        url: String,
+        browsers: Vec<BrowserShow>,
+        settings: &crate::settings::Settings,
+        usage: crate::usage::Usage,
+        mut window_state: crate::state::WindowState,
+    ) -> Self {
+        let layout = CardLayout::new(settings.layout, settings.card_width, settings.card_height);
+        let default_size = layout.window_size(settings.cols, settings.rows);
+        window_state.default_size = Some(default_size);
+        let editing_url = url.trim().is_empty();
+        let mut window = Self {
             url,
             browsers,
-            cols,
+            layout,
+            window_width: default_size.0,
+            usage,
+            order_by_usage: settings.order_by_usage,
+            url_id: egui::Id::new("url"),
+            editing_url,
+            focus_url: false,
+            url_edited: false,
+            search: String::new(),
+            search_id: egui::Id::new("search"),
+            focus_search: true,
+            visible: Vec::new(),
+            selected: None,
+            nav_keys: Vec::new(),
+            type_ahead: String::new(),
+            type_ahead_time: 0.0,
+            scroll_to_selected: false,
+            history_limit: settings.history_limit,
+            show_history: false,
+            history: Vec::new(),
+            history_search: String::new(),
+            saved_window_state: window_state.clone(),
+            window_state,
+            timeout: settings.timeout_secs as f64,
+            deadline: (settings.timeout_secs > 0).then_some(settings.timeout_secs as f64),
+            pointer_origin: None,
+            default_browser: settings.default_browser.clone(),
+            focus_loss: settings.focus_loss,
+            was_focused: false,
+            launch_error: None,
+            picked: Vec::new(),
+            editor: match &settings.editor {
+                Some(info) => BrowserExec::from_info(info),
+                None => BrowserExec {
+                    name: "Notepad".to_string(),
+                    path: "notepad.exe".to_string(),
+                    cmd: Vec::new(),
+                    launch: Default::default(),
+                },
+            },
+            policy: settings.link_policy(),
+            blocklist: crate::blocklist::Blocklist::load(&settings.blocklists),
+            block_action: settings.blocklist_action,
+            block_override: None,
+            rewriter: crate::rewrite::Rewriter::new(&settings.rewrites),
+            rewrite: None,
+            rewrite_undone: false,
+        };
+        window.load_url(window.url.clone());
+        window
+    }
+
+    /// Show `url` as an incoming URL, with the rewrite rules applied afresh
+    fn load_url(&mut self, url: String) {
+        self.url = url;
+        self.url_edited = false;
+        self.apply_rewrites();
+        self.refresh_visible();
+    }
+
+    /// Filter and rank the browsers against the search text
+    fn refresh_visible(&mut self) {
+        let query = self.search.trim();
+        let host = crate::link::host(&self.url);
+        let mut ranked: Vec<(usize, i32)> = self
+            .browsers
+            .iter()
+            .enumerate()
+            .filter_map(|(i, b)| Some((i, crate::fuzzy::score_browser(query, &b.name, &b.path)?)))
+            .collect();
+        // Stable, so equally good matches keep the settings order
+        ranked.sort_by_key(|&(i, score)| {
+            let launches = if self.order_by_usage {
+                self.usage.score(&self.browsers[i].name, host.as_deref())
+            } else {
+                0
+            };
+            std::cmp::Reverse((score, launches))
+        });
+        self.visible = ranked.into_iter().map(|(i, _)| i).collect();
+
+        // Enter launches the top match while searching
+        self.selected = if query.is_empty() || self.visible.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+    }
+
+    /// Move the focus ring according to a navigation key, returns the card to launch on Enter
+    fn navigate(&mut self, key: NavKey) -> Option<usize> {
+        let count = self.visible.len();
+        let columns = self.layout.columns(self.window_width, count);
+        if count == 0 {
+            return None;
+        }
+        let Some(current) = self.selected else {
+            // The first key only shows where the ring is
+            if key != NavKey::Activate {
+                self.select(0);
+            }
+            return None;
+        };
+        let next = match key {
+            NavKey::Left => current.saturating_sub(1),
+            NavKey::Right => (current + 1).min(count - 1),
+            NavKey::Up => current.checked_sub(columns).unwrap_or(current),
+            NavKey::Down => (current + columns).min(count - 1),
+            NavKey::Next => (current + 1) % count,
+            NavKey::Previous => (current + count - 1) % count,
+            NavKey::Activate => return Some(self.visible[current]),
+        };
+        self.select(next);
+        None
+    }
+
+    /// Jump to the first browser whose name starts with the letters typed so far
+    fn type_to_select(&mut self, text: &str, now: f64) {
+        if now - self.type_ahead_time > TYPE_AHEAD_TIMEOUT {
+            self.type_ahead.clear();
+        }
+        self.type_ahead_time = now;
+        self.type_ahead.push_str(&text.to_lowercase());
+
+        let find = |prefix: &str| {
+            self.visible
+                .iter()
+                .position(|&i| self.browsers[i].name.to_lowercase().starts_with(prefix))
+        };
+        // Fall back to the latest letter alone, so a typo doesn't stop the search
+        let found = find(&self.type_ahead).or_else(|| {
+            let found = find(&text.to_lowercase());
+            if found.is_some() {
+                self.type_ahead = text.to_lowercase();
+            }
+            found
+        });
+        if let Some(index) = found {
+            self.select(index);
         }
     }
+
+    /// Browser or group of the default action, None means just closing the window
+    fn default_index(&self) -> Option<usize> {
+        let name = self.default_browser.as_ref()?;
+        let index = self
+            .browsers
+            .iter()
+            .position(|b| b.name.eq_ignore_ascii_case(name));
+        if index.is_none() {
+            log::warn!("Default browser {name} is not in the browser list");
+        }
+        index
+    }
+
+    /// Browsers to start for a card, every member of a group or the browser itself
+    fn targets(&mut self, index: usize) -> Vec<BrowserExec> {
+        let card = &self.browsers[index];
+        if card.members.is_empty() {
+            return vec![card.to_exec()];
+        }
+        let mut execs = Vec::new();
+        for name in &card.members {
+            let member = self
+                .browsers
+                .iter()
+                .find(|b| b.members.is_empty() && b.name.eq_ignore_ascii_case(name));
+            match member {
+                Some(member) => execs.push(member.to_exec()),
+                None => log::warn!(
+                    "Group {} member {name} is not in the browser list",
+                    card.name
+                ),
+            }
+        }
+        if execs.is_empty() {
+            self.launch_error = Some(LaunchError {
+                browser: card.name.clone(),
+                message: "None of the group members are in the browser list".to_string(),
+            });
+        }
+        execs
+    }
+
+    /// Browsers of every picked card, each one once
+    fn picked_targets(&mut self) -> Vec<BrowserExec> {
+        let mut execs: Vec<BrowserExec> = Vec::new();
+        for index in self.picked.clone() {
+            for exec in self.targets(index) {
+                if !execs.iter().any(|e| e.name == exec.name) {
+                    execs.push(exec);
+                }
+            }
+        }
+        execs
+    }
+
+    fn toggle_pick(&mut self, index: usize) {
+        match self.picked.iter().position(|&i| i == index) {
+            Some(pos) => {
+                self.picked.remove(pos);
+            }
+            None => self.picked.push(index),
+        }
+    }
+
+    /// Editor button and file URL warnings for local files, returns whether to open the editor
+    fn local_file_bar(&self, ui: &mut egui::Ui) -> bool {
+        if crate::link::file_path(&self.url).is_none() {
+            return false;
+        }
+        let mut open_editor = false;
+        ui.horizontal(|ui| {
+            open_editor = ui
+                .button(format!("\u{f044} Open in {}", self.editor.name))
+                .clicked();
+            let refusing: Vec<&str> = self
+                .visible
+                .iter()
+                .map(|&i| &self.browsers[i])
+                .filter(|b| b.launch.no_file_urls)
+                .map(|b| b.name.as_str())
+                .collect();
+            if !refusing.is_empty() {
+                ui.add(
+                    egui::Label::new(
+                        egui::RichText::new(format!(
+                            "\u{f071} {} can't open local files",
+                            refusing.join(", ")
+                        ))
+                        .color(ui.visuals().warn_fg_color),
+                    )
+                    .truncate(),
+                );
+            }
+        });
+        open_editor
+    }
+
+    /// Picked cards with buttons to open or drop them, returns the browsers to open
+    fn picked_bar(&mut self, ui: &mut egui::Ui) -> Vec<BrowserExec> {
+        if self.picked.is_empty() {
+            return Vec::new();
+        }
+        let mut open = false;
+        ui.horizontal(|ui| {
+            open = ui
+                .button("\u{f0e7} Open all")
+                .on_hover_text("Enter")
+                .clicked();
+            if ui.button("\u{ea76} Clear").clicked() {
+                self.picked.clear();
+            }
+            let names: Vec<&str> = self
+                .picked
+                .iter()
+                .map(|&i| self.browsers[i].name.as_str())
+                .collect();
+            ui.add(egui::Label::new(names.join(", ")).truncate());
+        });
+        if open {
+            self.picked_targets()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Blocklist entry for the current URL with the URL as it would be launched
+    fn blocked(&self) -> Option<(String, crate::blocklist::BlockMatch)> {
+        let url = crate::link::canonicalize(&self.url, &self.policy).ok()?;
+        let found = self.blocklist.check(&crate::link::host(&url)?)?;
+        Some((url, found))
+    }
+
+    /// Start the browser with the current URL and record the launch, returns whether it started
+    fn launch(&mut self, exec: &BrowserExec, routing: crate::history::Routing) -> bool {
+        if let Some((url, found)) = self.blocked() {
+            let overridden = self.block_override.as_ref() == Some(&url);
+            if self.block_action == crate::blocklist::BlockAction::Refuse && !overridden {
+                log::warn!(
+                    "Refused {url}, {} is on blocklist {}",
+                    found.domain,
+                    found.list
+                );
+                self.launch_error = Some(LaunchError {
+                    browser: exec.name.clone(),
+                    message: format!("{} is on blocklist {}", found.domain, found.list),
+                });
+                return false;
+            }
+            log::warn!(
+                "Opening {url} although {} is on blocklist {}",
+                found.domain,
+                found.list
+            );
+        }
+        match exec.spawn(&self.url, &self.policy) {
+            Err(e) => {
+                log::error!("Failed to open browser: {}", e);
+                self.launch_error = Some(LaunchError::new(exec, &e));
+                false
+            }
+            Ok(url) => {
+                log::info!(
+                    "Opened browser: {:?}",
+                    exec.path.clone() + " " + &exec.cmd.join(" ")
+                );
+                let entry = crate::history::HistoryEntry::new(&url, &exec.name, routing);
+                if let Err(e) = crate::history::append(&entry, self.history_limit) {
+                    log::error!("Failed to write history: {e}");
+                }
+                let host = crate::link::host(&url);
+                self.usage.record(&exec.name, host.as_deref());
+                if let Err(e) = self.usage.save() {
+                    log::error!("Failed to save usage: {e}");
+                }
+                true
+            }
+        }
+    }
+
+    /// The URL with its registrable domain highlighted and the rest dimmed, click to edit
+    fn url_view(&mut self, ui: &mut egui::Ui) {
+        let Some(parts) = crate::link::split_for_display(&self.url) else {
+            if ui
+                .add(
+                    egui::Label::new(&self.url)
+                        .wrap()
+                        .sense(egui::Sense::click()),
+                )
+                .on_hover_text("Click to edit")
+                .clicked()
+            {
+                self.editing_url = true;
+                self.focus_url = true;
+            }
+            return;
+        };
+
+        let font = egui::TextStyle::Body.resolve(ui.style());
+        let dim = egui::TextFormat::simple(font.clone(), ui.visuals().weak_text_color());
+        let mut job = egui::text::LayoutJob::default();
+        job.append(&parts.prefix, 0.0, dim.clone());
+        job.append(
+            &parts.subdomain,
+            0.0,
+            egui::TextFormat::simple(font.clone(), ui.visuals().text_color()),
+        );
+        job.append(
+            &parts.domain,
+            0.0,
+            egui::TextFormat {
+                font_id: font,
+                color: ui.visuals().strong_text_color(),
+                background: ui.visuals().selection.bg_fill.gamma_multiply(0.4),
+                ..Default::default()
+            },
+        );
+        job.append(&parts.rest, 0.0, dim);
+        if ui
+            .add(egui::Label::new(job).wrap().sense(egui::Sense::click()))
+            .on_hover_text("Click to edit")
+            .clicked()
+        {
+            self.editing_url = true;
+            self.focus_url = true;
+        }
+
+        if !parts.query.is_empty() {
+            egui::CollapsingHeader::new(format!("{} query parameters", parts.query.len()))
+                .id_salt("query")
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_height(120.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("query_table")
+                                .striped(true)
+                                .num_columns(2)
+                                .show(ui, |ui| {
+                                    for (key, value) in &parts.query {
+                                        ui.strong(key);
+                                        ui.add(egui::Label::new(value).wrap());
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                });
+        }
+    }
+
+    /// Run the rewrite rules on the URL, remembering the original for undo
+    fn apply_rewrites(&mut self) {
+        self.rewrite = self.rewriter.apply(&self.url);
+        self.rewrite_undone = false;
+        if let Some(rewrite) = &self.rewrite {
+            log::info!(
+                "Rewrote {} to {} ({})",
+                rewrite.original,
+                rewrite.rewritten,
+                rewrite.applied.join(", ")
+            );
+            self.url = rewrite.rewritten.clone();
+        }
+    }
+
+    /// Rules that changed the URL, with a toggle back to the original
+    fn rewrite_row(&mut self, ui: &mut egui::Ui) {
+        let Some(rewrite) = &self.rewrite else {
+            return;
+        };
+        let mut undone = self.rewrite_undone;
+        ui.horizontal(|ui| {
+            ui.toggle_value(&mut undone, "\u{f0e2} Undo rewrite")
+                .on_hover_text(&rewrite.original);
+            let text = if undone {
+                format!("Original: {}", rewrite.original)
+            } else {
+                format!("Rewritten: {}", rewrite.applied.join(", "))
+            };
+            ui.add(egui::Label::new(egui::RichText::new(text).weak()).truncate());
+        });
+        if undone != self.rewrite_undone {
+            self.rewrite_undone = undone;
+            self.url = if undone {
+                rewrite.original.clone()
+            } else {
+                rewrite.rewritten.clone()
+            };
+        }
+    }
+
+    /// Decoded and raw host for international names, with warnings for lookalike domains
+    fn host_row(&self, ui: &mut egui::Ui) {
+        let Some(host) = crate::link::inspect_host(&self.url) else {
+            return;
+        };
+        if host.unicode != host.raw {
+            ui.horizontal(|ui| {
+                ui.label(format!("\u{f0ac} {}", host.unicode));
+                ui.weak(&host.raw);
+            });
+        }
+        if !host.warnings.is_empty() {
+            egui::Frame::group(ui.style())
+                .stroke(egui::Stroke::new(1.0, ui.visuals().warn_fg_color))
+                .show(ui, |ui| {
+                    for warning in &host.warnings {
+                        ui.colored_label(ui.visuals().warn_fg_color, format!("\u{f071} {warning}"));
+                    }
+                });
+        }
+    }
+
+    /// Red warning for a blocklisted host, with the override when such URLs are refused
+    fn blocklist_banner(&mut self, ui: &mut egui::Ui) {
+        let Some((url, found)) = self.blocked() else {
+            return;
+        };
+        let color = ui.visuals().error_fg_color;
+        egui::Frame::group(ui.style())
+            .stroke(egui::Stroke::new(1.0, color))
+            .fill(color.gamma_multiply(0.15))
+            .show(ui, |ui| {
+                ui.colored_label(
+                    color,
+                    format!("\u{f05e} {} is on blocklist {}", found.domain, found.list),
+                );
+                if self.block_action != crate::blocklist::BlockAction::Refuse {
+                    return;
+                }
+                if self.block_override.as_ref() == Some(&url) {
+                    ui.weak("Opening anyway");
+                } else if ui.button("\u{f071} Open anyway").clicked() {
+                    log::warn!(
+                        "Blocklist overridden for {url}, {} is on blocklist {}",
+                        found.domain,
+                        found.list
+                    );
+                    self.block_override = Some(url);
+                    self.launch_error = None;
+                }
+            });
+    }
+
+    /// Warning for a URL that can't be launched, shown before anyone tries
+    fn blocked_banner(&self, ui: &mut egui::Ui) {
+        // An empty URL is still being typed, not blocked
+        if self.url.trim().is_empty() {
+            return;
+        }
+        if let Err(e) = crate::link::canonicalize(&self.url, &self.policy) {
+            egui::Frame::group(ui.style())
+                .stroke(egui::Stroke::new(1.0, ui.visuals().error_fg_color))
+                .show(ui, |ui| {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        format!("\u{f05e} This URL will not be opened: {e}"),
+                    );
+                });
+        }
+    }
+
+    /// Error of the last launch with ways out, returns whether the settings should be opened
+    fn launch_error_banner(&mut self, ui: &mut egui::Ui) -> bool {
+        let Some(error) = self.launch_error.clone() else {
+            return false;
+        };
+        let mut edit_cmd = false;
+        egui::Frame::group(ui.style())
+            .stroke(egui::Stroke::new(1.0, ui.visuals().error_fg_color))
+            .show(ui, |ui| {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("\u{f071} {}: {}", error.browser, error.message),
+                );
+                ui.horizontal(|ui| {
+                    edit_cmd = ui
+                        .button("\u{eb52} Edit entry")
+                        .on_hover_text("Open the settings file")
+                        .clicked();
+                    if ui.button("\u{f0e2} Try another browser").clicked() {
+                        self.launch_error = None;
+                        // Move the selection off the browser that failed
+                        let other = self
+                            .visible
+                            .iter()
+                            .position(|&i| self.browsers[i].name != error.browser);
+                        if let Some(index) = other {
+                            self.select(index);
+                        }
+                        self.show_history = false;
+                    }
+                });
+            });
+        edit_cmd
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = Some(index);
+        self.scroll_to_selected = true;
+    }
+
+    /// Cards of the visible browsers in the configured layout, returns the index of the one clicked
+    fn browser_grid(&self, ui: &mut egui::Ui, scroll_to_selected: bool) -> Option<usize> {
+        let mut clicked_browser = None;
+        let columns = self.layout.columns(self.window_width, self.visible.len());
+        egui::ScrollArea::new([true, true])
+            .max_width(ui.available_width())
+            .max_height(ui.available_height())
+            .show(ui, |ui| {
+                for (row, chunk) in self.visible.chunks(columns).enumerate() {
+                    ui.horizontal(|ui| {
+                        for (col, &browser_index) in chunk.iter().enumerate() {
+                            let index = row * columns + col;
+                            let browser = &self.browsers[browser_index];
+                            let (rect, clicked) = self.card(ui, browser);
+                            if clicked {
+                                clicked_browser = Some(browser_index);
+                            }
+                            if browser.launch.no_file_urls
+                                && crate::link::file_path(&self.url).is_some()
+                            {
+                                ui.painter().text(
+                                    rect.right_bottom() + egui::vec2(-4.0, -4.0),
+                                    egui::Align2::RIGHT_BOTTOM,
+                                    "\u{f071}",
+                                    egui::FontId::proportional(14.0),
+                                    ui.visuals().warn_fg_color,
+                                );
+                            }
+                            if self.picked.contains(&browser_index) {
+                                let color = ui.visuals().selection.bg_fill;
+                                ui.painter().rect_filled(
+                                    rect,
+                                    ui.visuals().widgets.hovered.corner_radius,
+                                    color.gamma_multiply(0.3),
+                                );
+                                ui.painter().text(
+                                    rect.left_top() + egui::vec2(4.0, 4.0),
+                                    egui::Align2::LEFT_TOP,
+                                    "\u{f00c}",
+                                    egui::FontId::proportional(14.0),
+                                    ui.visuals().strong_text_color(),
+                                );
+                            }
+                            let failed = self
+                                .launch_error
+                                .as_ref()
+                                .is_some_and(|e| e.browser == browser.name);
+                            if failed {
+                                let color = ui.visuals().error_fg_color;
+                                ui.painter().rect_stroke(
+                                    rect,
+                                    ui.visuals().widgets.hovered.corner_radius,
+                                    egui::Stroke::new(2.0, color),
+                                    egui::StrokeKind::Inside,
+                                );
+                                ui.painter().text(
+                                    rect.right_top() + egui::vec2(-4.0, 4.0),
+                                    egui::Align2::RIGHT_TOP,
+                                    "\u{f071}",
+                                    egui::FontId::proportional(14.0),
+                                    color,
+                                );
+                            }
+                            if self.selected == Some(index) {
+                                ui.painter().rect_stroke(
+                                    rect,
+                                    ui.visuals().widgets.hovered.corner_radius,
+                                    ui.visuals().selection.stroke,
+                                    egui::StrokeKind::Outside,
+                                );
+                                if scroll_to_selected {
+                                    ui.scroll_to_rect(rect, None);
+                                }
+                            }
+                        }
+                    });
+                }
+            });
+        clicked_browser
+    }
+
+    /// A single browser card, returns its area and whether it was clicked
+    fn card(&self, ui: &mut egui::Ui, browser: &BrowserShow) -> (egui::Rect, bool) {
+        let icon_size = self.layout.icon_size();
+        let image = egui::Image::from_texture(egui::load::SizedTexture::new(
+            browser.icon,
+            [browser.width, browser.height],
+        ))
+        .fit_to_exact_size(egui::vec2(icon_size, icon_size));
+
+        if self.layout.mode == Layout::List {
+            // Small icon with the full name next to it
+            let response = ui.add(
+                egui::Button::image_and_text(image, &browser.name)
+                    .min_size(self.layout.card_size())
+                    .truncate(),
+            );
+            return (response.rect, response.clicked());
+        }
+
+        let mut cur = ui.cursor();
+        cur.set_width(self.layout.card_size().x);
+        cur.set_height(self.layout.card_size().y);
+        let card = ui.scope_builder(egui::UiBuilder::new().max_rect(cur), |ui| {
+            ui.vertical(|ui| {
+                let clicked = ui.add(egui::ImageButton::new(image)).clicked();
+                ui.add_sized(
+                    egui::vec2(icon_size, self.layout.card_size().y - icon_size),
+                    egui::Label::new(&browser.name),
+                )
+                .on_hover_text(&browser.name);
+                clicked
+            })
+            .inner
+        });
+        (card.response.rect, card.inner)
+    }
+
+    /// Searchable list of handled URLs, returns the browser to re-open an entry in
+    fn history_panel(&mut self, ui: &mut egui::Ui) -> Option<BrowserExec> {
+        let mut open_browser = None;
+        let mut load_url = None;
+        ui.add(
+            egui::TextEdit::singleline(&mut self.history_search)
+                .hint_text("\u{f002} Search history")
+                .desired_width(ui.available_width()),
+        );
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height())
+            .show(ui, |ui| {
+                for entry in self
+                    .history
+                    .iter()
+                    .rev()
+                    .filter(|e| e.matches(&self.history_search))
+                {
+                    ui.horizontal(|ui| {
+                        let browser = self.browsers.iter().find(|b| b.name == entry.browser);
+                        if ui
+                            .add_enabled(browser.is_some(), egui::Button::new("\u{f0e2}"))
+                            .on_hover_text(format!("Open again in {}", entry.browser))
+                            .clicked()
+                        {
+                            open_browser = browser.map(BrowserShow::to_exec);
+                            load_url = Some(entry.url.clone());
+                        }
+                        ui.weak(entry.time_string());
+                        if entry.routing == crate::history::Routing::Automatic {
+                            ui.weak("\u{f0e7}").on_hover_text("Routed automatically");
+                        }
+                        ui.label(&entry.browser);
+                        if ui
+                            .add(
+                                egui::Label::new(&entry.url)
+                                    .truncate()
+                                    .sense(egui::Sense::click()),
+                            )
+                            .on_hover_text("Pick a browser for this URL")
+                            .clicked()
+                        {
+                            load_url = Some(entry.url.clone());
+                        }
+                    });
+                }
+            });
+        if let Some(url) = load_url {
+            self.show_history = false;
+            self.load_url(url);
+        }
+        open_browser
+    }
+}
+
+/// Handle in the bottom right corner to resize the undecorated window
+fn resize_grip(ctx: &egui::Context) {
+    egui::Area::new(egui::Id::new("resize_grip"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            let (rect, response) =
+                ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::drag());
+            let stroke = ui.visuals().widgets.noninteractive.fg_stroke;
+            for offset in [4.0, 8.0] {
+                ui.painter().line_segment(
+                    [
+                        egui::pos2(rect.right() - offset, rect.bottom()),
+                        egui::pos2(rect.right(), rect.bottom() - offset),
+                    ],
+                    stroke,
+                );
+            }
+            if response.drag_started() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::BeginResize(
+                    egui::ResizeDirection::SouthEast,
+                ));
+            }
+            response.on_hover_cursor(egui::CursorIcon::ResizeSouthEast);
+        });
 }
 
 impl eframe::App for MainWindow {
+    fn raw_input_hook(&mut self, ctx: &egui::Context, raw_input: &mut egui::RawInput) {
+        // The search box keeps text editing keys and passes on the rest
+        let searching = ctx.memory(|m| m.has_focus(self.search_id));
+        // Leave every key to other text fields, like the URL, while they are being edited
+        if ctx.wants_keyboard_input() && !searching {
+            return;
+        }
+        // Left and Right move the caret in the search text, at its edges they move the ring
+        let caret = egui::text_edit::TextEditState::load(ctx, self.search_id)
+            .and_then(|state| state.cursor.char_range())
+            .and_then(|range| range.single())
+            .map(|cursor| cursor.index);
+        let at_start = !searching || self.search.is_empty() || caret == Some(0);
+        let at_end =
+            !searching || self.search.is_empty() || caret == Some(self.search.chars().count());
+        let now = raw_input.time.unwrap_or_default();
+        let mut typed = String::new();
+        raw_input.events.retain(|event| match event {
+            egui::Event::Key {
+                key,
+                pressed,
+                modifiers,
+                ..
+            } if modifiers.is_none() || modifiers.matches_exact(egui::Modifiers::SHIFT) => {
+                let nav = match key {
+                    egui::Key::ArrowLeft if at_start => NavKey::Left,
+                    egui::Key::ArrowRight if at_end => NavKey::Right,
+                    egui::Key::ArrowUp => NavKey::Up,
+                    egui::Key::ArrowDown => NavKey::Down,
+                    egui::Key::Tab if modifiers.shift => NavKey::Previous,
+                    egui::Key::Tab => NavKey::Next,
+                    egui::Key::Enter => NavKey::Activate,
+                    _ => return true,
+                };
+                if *pressed {
+                    self.nav_keys.push(nav);
+                }
+                false
+            }
+            egui::Event::Text(text) if !searching => {
+                typed.push_str(text);
+                false
+            }
+            _ => true,
+        });
+        // Type-ahead never reaches `update`, so it stops the countdown here
+        if !typed.is_empty() {
+            self.deadline = None;
+        }
+        if !typed.trim().is_empty() {
+            self.type_to_select(typed.trim(), now);
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if self.window_state != self.saved_window_state {
+            if let Err(e) = self.window_state.save() {
+                log::error!("Failed to save window state: {e}");
+            }
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if let Some(rect) = ctx.input(|i| i.viewport().outer_rect) {
+            self.window_state.position = Some((rect.min.x, rect.min.y));
+            self.window_state.scale = ctx.input(|i| i.viewport().native_pixels_per_point);
+        }
+        if let Some(rect) = ctx.input(|i| i.viewport().inner_rect) {
+            self.window_state.size = Some((rect.width(), rect.height()));
+            self.window_width = rect.width();
+        }
         let copyshortcut = egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::S);
         let exitshortcut_0 = egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::Q);
         let exitshortcut_1 = egui::KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::Escape);
@@ -106,18 +1187,94 @@ impl eframe::App for MainWindow {
         let mut exit_cmd = false;
         let mut settings_cmd = false;
         let mut log_cmd = false;
-        let mut open_browser = None;
+        let mut history_cmd = false;
+        let mut editor_cmd = false;
+        let mut open_browser = Vec::new();
+        let mut routing = crate::history::Routing::Manual;
+        let mut default_action = false;
         ctx.input_mut(|r| {
             copy_cmd = copy_cmd || r.consume_shortcut(&copyshortcut);
             exit_cmd = exit_cmd
                 || r.consume_shortcut(&exitshortcut_0)
                 || r.consume_shortcut(&exitshortcut_1);
-            for i in 0..open_shortcuts.len() {
-                if r.consume_shortcut(&open_shortcuts[i]) && i < self.browsers.len() {
-                    open_browser = Some(self.browsers[i].into_exec());
+            for (i, shortcut) in open_shortcuts.iter().enumerate() {
+                if r.consume_shortcut(shortcut) && i < self.visible.len() {
+                    open_browser = self.targets(self.visible[i]);
                 }
             }
         });
+        if let Some(deadline) = self.deadline {
+            // Any sign of the user stops the countdown. A pointer resting over the window does not,
+            // with cursor placement the window opens under it and gets a move it never made.
+            let interacted = !self.nav_keys.is_empty()
+                || ctx.input(|i| {
+                    i.events.iter().any(|e| match e {
+                        egui::Event::PointerMoved(pos) => {
+                            *self.pointer_origin.get_or_insert(*pos) != *pos
+                        }
+                        egui::Event::PointerButton { .. }
+                        | egui::Event::MouseWheel { .. }
+                        | egui::Event::Key { .. }
+                        | egui::Event::Text(_) => true,
+                        _ => false,
+                    })
+                });
+            if interacted {
+                self.deadline = None;
+            } else if ctx.input(|i| i.time) >= deadline {
+                log::info!("No choice within {}s", self.timeout);
+                self.deadline = None;
+                default_action = true;
+            } else {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+            }
+        }
+        match ctx.input(|i| i.viewport().focused) {
+            Some(true) => self.was_focused = true,
+            Some(false) if std::mem::take(&mut self.was_focused) => {
+                log::info!("Window lost focus");
+                match self.focus_loss {
+                    FocusLoss::Stay => {}
+                    FocusLoss::Close => exit_cmd = true,
+                    FocusLoss::DefaultAction => default_action = true,
+                }
+            }
+            _ => {}
+        }
+        if default_action {
+            match self.default_index() {
+                Some(index) => {
+                    open_browser = self.targets(index);
+                    routing = crate::history::Routing::Automatic;
+                }
+                None => exit_cmd = true,
+            }
+        }
+        for key in std::mem::take(&mut self.nav_keys) {
+            if let Some(index) = self.navigate(key) {
+                // With cards picked, Enter opens all of them instead of the focused one
+                open_browser = if self.picked.is_empty() {
+                    self.targets(index)
+                } else {
+                    self.picked_targets()
+                };
+            }
+        }
+        let scroll_to_selected = std::mem::take(&mut self.scroll_to_selected);
+        if let Some(deadline) = self.deadline {
+            let remaining = (deadline - ctx.input(|i| i.time)).max(0.0);
+            let text = match self.default_index() {
+                Some(index) => format!(
+                    "Opening in {} in {:.0}s",
+                    self.browsers[index].name,
+                    remaining.ceil()
+                ),
+                None => format!("Closing in {:.0}s", remaining.ceil()),
+            };
+            egui::TopBottomPanel::bottom("countdown").show(ctx, |ui| {
+                ui.add(egui::ProgressBar::new((remaining / self.timeout) as f32).text(text));
+            });
+        }
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
                 let (drag_resp, _) = ui
@@ -133,76 +1290,112 @@ impl eframe::App for MainWindow {
                 settings_cmd = ui.button("\u{eb52}").clicked();
                 copy_cmd = copy_cmd || ui.button("\u{ebcc}").on_hover_text("Ctrl + S").clicked();
                 log_cmd = ui.button("\u{f4ed}").clicked();
-                ui.add(
-                    egui::TextEdit::singleline(&mut self.url).desired_width(ui.available_width()),
-                )
-            });
-            egui::ScrollArea::new([true, true])
-                .max_width(ui.available_width())
-                .max_height(ui.available_height())
-                .show(ui, |ui| {
-                    for i in 0..(self.browsers.len() / self.cols + 1) {
-                        ui.horizontal(|ui| {
-                            for j in 0..self.cols {
-                                let index = i * self.cols + j;
-                                if index < self.browsers.len() {
-                                    let browser = &self.browsers[index];
-                                    let mut cur = ui.cursor();
-                                    cur.set_width(CARD_WIDTH);
-                                    cur.set_height(CARD_HEIGHT);
-                                    ui.scope_builder(egui::UiBuilder::new().max_rect(cur), |ui| {
-                                        ui.vertical(|ui| {
-                                            if ui
-                                                .add(egui::ImageButton::new(
-                                                    egui::Image::from_texture(
-                                                        egui::load::SizedTexture::new(
-                                                            browser.icon,
-                                                            [browser.width, browser.height],
-                                                        ),
-                                                    )
-                                                    .fit_to_exact_size(egui::vec2(
-                                                        CARD_WIDTH, CARD_WIDTH,
-                                                    )),
-                                                ))
-                                                .clicked()
-                                            {
-                                                open_browser = Some(browser.into_exec());
-                                            }
-                                            ui.add_sized(
-                                                egui::vec2(CARD_WIDTH, CARD_HEIGHT - CARD_WIDTH),
-                                                egui::Label::new(&browser.name),
-                                            );
-                                        });
-                                    });
-                                } else {
-                                    break;
-                                }
-                            }
-                        });
+                history_cmd = ui
+                    .selectable_label(self.show_history, "\u{f1da}")
+                    .on_hover_text("History")
+                    .clicked();
+                if ui
+                    .selectable_label(self.editing_url, "\u{f044}")
+                    .on_hover_text("Edit URL")
+                    .clicked()
+                {
+                    self.editing_url = !self.editing_url;
+                    self.focus_url = self.editing_url;
+                }
+                if self.editing_url {
+                    let edit = ui.add(
+                        egui::TextEdit::singleline(&mut self.url)
+                            .id(self.url_id)
+                            .desired_width(ui.available_width()),
+                    );
+                    if std::mem::take(&mut self.focus_url) {
+                        edit.request_focus();
                     }
-                });
+                    if edit.lost_focus() && !self.url.trim().is_empty() {
+                        self.editing_url = false;
+                    }
+                    self.url_edited |= edit.changed();
+                    if edit.lost_focus() && std::mem::take(&mut self.url_edited) {
+                        self.apply_rewrites();
+                    }
+                }
+            });
+            if !self.editing_url {
+                self.url_view(ui);
+            }
+            if history_cmd {
+                self.show_history = !self.show_history;
+                if self.show_history {
+                    self.history = crate::history::load();
+                }
+            }
+            let search = ui.add(
+                egui::TextEdit::singleline(&mut self.search)
+                    .id(self.search_id)
+                    .hint_text("\u{f002} Search")
+                    .desired_width(ui.available_width()),
+            );
+            if std::mem::take(&mut self.focus_search) {
+                search.request_focus();
+            }
+            if search.changed() {
+                self.refresh_visible();
+            }
+            self.rewrite_row(ui);
+            self.host_row(ui);
+            self.blocked_banner(ui);
+            self.blocklist_banner(ui);
+            settings_cmd = self.launch_error_banner(ui) || settings_cmd;
+            editor_cmd = self.local_file_bar(ui);
+            let picked = self.picked_bar(ui);
+            if !picked.is_empty() {
+                open_browser = picked;
+            }
+            if self.show_history {
+                if let Some(exec) = self.history_panel(ui) {
+                    open_browser = vec![exec];
+                }
+            } else if let Some(index) = self.browser_grid(ui, scroll_to_selected) {
+                if ui.input(|i| i.modifiers.command) {
+                    self.toggle_pick(index);
+                } else {
+                    open_browser = self.targets(index);
+                }
+            }
         });
+        resize_grip(ctx);
         if copy_cmd {
             ctx.copy_text(self.url.clone());
         }
         if exit_cmd {
             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
         }
-        if let Some(exec) = open_browser {
-            let mut cmds = exec.cmd.clone();
-            cmds.push(self.url.clone());
-            let result = std::process::Command::new(exec.path.clone())
-                .args(cmds)
-                .spawn();
-            if let Err(e) = result {
-                log::error!("Failed to open browser: {}", e);
-            } else {
-                log::info!(
-                    "Opened browser: {:?}",
-                    exec.path.clone() + " " + &exec.cmd.join(" ")
-                );
+        if editor_cmd {
+            let path = crate::link::file_path(&self.url).unwrap_or_default();
+            match self.editor.spawn_argument(&path) {
+                Ok(()) => {
+                    log::info!("Opened {path} in {}", self.editor.name);
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+                Err(e) => {
+                    log::error!("Failed to open editor: {e}");
+                    self.launch_error = Some(LaunchError::new(&self.editor, &e));
+                }
             }
-            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+        if !open_browser.is_empty() {
+            let mut started = true;
+            for exec in &open_browser {
+                started &= self.launch(exec, routing);
+            }
+            // Stay open after a failure so another browser can be picked
+            if started {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }
+        if settings_cmd || log_cmd {
+            // The editor takes the focus, don't treat that as dismissing the picker
+            self.was_focused = false;
         }
         if settings_cmd {
             // use default text editor to open settings file
@@ -0,0 +1,148 @@
+use crate::utils::WorkArea;
+use serde::{Deserialize, Serialize};
+
+// Cursor placement keeps the pointer this far below the top edge, over the toolbar
+const CURSOR_OFFSET_Y: f32 = 20.0;
+
+/// Where the picker window opens
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Placement {
+    /// Centered on the primary monitor
+    Primary,
+    /// Centered on the monitor of the foreground window
+    #[default]
+    ActiveMonitor,
+    /// Under the mouse cursor
+    Cursor,
+    /// Where the window was when it was last closed
+    LastPosition,
+}
+
+/// Rectangle in logical points
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Area {
+    left: f32,
+    top: f32,
+    right: f32,
+    bottom: f32,
+}
+
+impl From<WorkArea> for Area {
+    fn from(area: WorkArea) -> Self {
+        Self {
+            left: area.left as f32 / area.scale,
+            top: area.top as f32 / area.scale,
+            right: area.right as f32 / area.scale,
+            bottom: area.bottom as f32 / area.scale,
+        }
+    }
+}
+
+impl Area {
+    fn center(&self, (width, height): (f32, f32)) -> (f32, f32) {
+        (
+            self.left + (self.right - self.left - width) / 2.0,
+            self.top + (self.bottom - self.top - height) / 2.0,
+        )
+    }
+
+    /// Move a window of `size` at `pos` so it is fully inside, top-left wins if it can't fit
+    fn clamp(&self, (width, height): (f32, f32), (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            x.min(self.right - width).max(self.left),
+            y.min(self.bottom - height).max(self.top),
+        )
+    }
+}
+
+/// Top-left window position in logical points for a window of `size`.
+/// `last_position` is in logical points of a monitor with `last_scale` pixels per point.
+pub fn window_position(
+    placement: Placement,
+    size: (f32, f32),
+    last_position: Option<(f32, f32)>,
+    last_scale: Option<f32>,
+) -> (f32, f32) {
+    // A window larger than the area keeps its toolbar, and with it the drag handle, on screen
+    let centered = |area: WorkArea| {
+        let area = Area::from(area);
+        area.clamp(size, area.center(size))
+    };
+    let position = match placement {
+        Placement::Primary => None,
+        Placement::ActiveMonitor => crate::utils::get_active_work_area().map(centered),
+        // The cursor is in physical pixels, converted to points with the scale of its monitor
+        Placement::Cursor => crate::utils::get_cursor_position().and_then(|(x, y)| {
+            let area = crate::utils::get_work_area_at(x, y)?;
+            let (x, y) = (x as f32 / area.scale, y as f32 / area.scale);
+            Some(Area::from(area).clamp(size, (x - size.0 / 2.0, y - CURSOR_OFFSET_Y)))
+        }),
+        // The saved position is in points, back to physical pixels for the lookup and then
+        // to points of the monitor found, in case its scale changed. Older state files have no scale.
+        Placement::LastPosition => match last_position {
+            Some((x, y)) => {
+                let scale = last_scale.unwrap_or(1.0);
+                let (x, y) = (x * scale, y * scale);
+                crate::utils::get_work_area_at(x as i64, y as i64).map(|area| {
+                    let (x, y) = (x / area.scale, y / area.scale);
+                    Area::from(area).clamp(size, (x, y))
+                })
+            }
+            None => crate::utils::get_active_work_area().map(centered),
+        },
+    };
+    position.unwrap_or_else(|| primary_center(size))
+}
+
+/// Centered on the primary monitor, the original placement
+fn primary_center((width, height): (f32, f32)) -> (f32, f32) {
+    let (sc_width, sc_height) = crate::utils::get_screen_size();
+    (
+        (sc_width as f32 - width) / 2.0,
+        (sc_height as f32 - height) / 2.0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND_MONITOR: WorkArea = WorkArea {
+        left: 1920,
+        top: 0,
+        right: 4800,
+        bottom: 1560,
+        scale: 1.5,
+    };
+
+    #[test]
+    fn work_area_is_converted_to_points() {
+        let area = Area::from(SECOND_MONITOR);
+        assert_eq!(
+            area,
+            Area {
+                left: 1280.0,
+                top: 0.0,
+                right: 3200.0,
+                bottom: 1040.0
+            }
+        );
+        assert_eq!(area.center((200.0, 100.0)), (2140.0, 470.0));
+    }
+
+    #[test]
+    fn window_is_clamped_into_work_area() {
+        let area = Area::from(SECOND_MONITOR);
+        assert_eq!(
+            area.clamp((200.0, 100.0), (3150.0, 1000.0)),
+            (3000.0, 940.0)
+        );
+        assert_eq!(area.clamp((200.0, 100.0), (1200.0, -30.0)), (1280.0, 0.0));
+        assert_eq!(area.clamp((200.0, 100.0), (1500.0, 500.0)), (1500.0, 500.0));
+
+        // Too large to fit, centering alone would put the top above the area
+        let size = (400.0, 1200.0);
+        assert_eq!(area.clamp(size, area.center(size)), (2040.0, 0.0));
+    }
+}
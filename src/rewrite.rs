@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+
+/// A user-defined change to incoming URLs, applied before they are shown and launched
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RewriteRule {
+    /// Regular expression over the whole URL, `replace` can use `$1` or `${name}`
+    Regex { pattern: String, replace: String },
+    /// Swap the host, `from` also matches its `www.` form
+    Host { from: String, to: String },
+    /// Switch `http` to `https` for these hosts and their subdomains
+    ForceHttps { hosts: Vec<String> },
+}
+
+impl RewriteRule {
+    /// Short form shown next to a rewritten URL
+    pub fn describe(&self) -> String {
+        match self {
+            RewriteRule::Regex { pattern, replace } => format!("{pattern} \u{2192} {replace}"),
+            RewriteRule::Host { from, to } => format!("{from} \u{2192} {to}"),
+            RewriteRule::ForceHttps { .. } => "https".to_string(),
+        }
+    }
+}
+
+/// Outcome of rewriting a URL
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rewrite {
+    pub original: String,
+    pub rewritten: String,
+    pub applied: Vec<String>, // Descriptions of the rules that changed something
+}
+
+/// Rules ready to apply, with regular expressions compiled once
+#[derive(Clone)]
+pub struct Rewriter {
+    rules: Vec<(RewriteRule, Option<regex::Regex>)>,
+}
+
+impl Rewriter {
+    /// Compile the rules, invalid patterns are logged and skipped
+    pub fn new(rules: &[RewriteRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| match rule {
+                RewriteRule::Regex { pattern, .. } => match regex::Regex::new(pattern) {
+                    Ok(regex) => Some((rule.clone(), Some(regex))),
+                    Err(e) => {
+                        log::error!("Skipping rewrite rule {pattern}: {e}");
+                        None
+                    }
+                },
+                _ => Some((rule.clone(), None)),
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Run every rule in order on `url`, None if nothing changed
+    pub fn apply(&self, url: &str) -> Option<Rewrite> {
+        let mut current = url.to_string();
+        let mut applied = Vec::new();
+        for (rule, regex) in &self.rules {
+            let next = match (rule, regex) {
+                (RewriteRule::Regex { replace, .. }, Some(regex)) => {
+                    Some(regex.replace_all(&current, replace.as_str()).into_owned())
+                }
+                (RewriteRule::Host { from, to }, _) => swap_host(&current, from, to),
+                (RewriteRule::ForceHttps { hosts }, _) => force_https(&current, hosts),
+                _ => None,
+            };
+            if let Some(next) = next.filter(|next| *next != current) {
+                applied.push(rule.describe());
+                current = next;
+            }
+        }
+        (current != url).then(|| Rewrite {
+            original: url.to_string(),
+            rewritten: current,
+            applied,
+        })
+    }
+}
+
+fn swap_host(url: &str, from: &str, to: &str) -> Option<String> {
+    let mut parsed = url::Url::parse(url).ok()?;
+    let host = parsed.host_str()?.to_lowercase();
+    let from = from.to_lowercase();
+    if host != from && host != format!("www.{from}") {
+        return None;
+    }
+    parsed.set_host(Some(to)).ok()?;
+    Some(parsed.to_string())
+}
+
+fn force_https(url: &str, hosts: &[String]) -> Option<String> {
+    let mut parsed = url::Url::parse(url).ok()?;
+    if parsed.scheme() != "http" {
+        return None;
+    }
+    let host = parsed.host_str()?.to_lowercase();
+    let listed = hosts.iter().any(|h| {
+        let h = h.to_lowercase();
+        host == h || host.ends_with(&format!(".{h}"))
+    });
+    if !listed {
+        return None;
+    }
+    parsed.set_scheme("https").ok()?;
+    Some(parsed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewriter() -> Rewriter {
+        Rewriter::new(&[
+            RewriteRule::Host {
+                from: "twitter.com".to_string(),
+                to: "nitter.local".to_string(),
+            },
+            RewriteRule::Host {
+                from: "reddit.com".to_string(),
+                to: "old.reddit.com".to_string(),
+            },
+            RewriteRule::ForceHttps {
+                hosts: vec!["example.com".to_string()],
+            },
+            RewriteRule::Regex {
+                pattern: r"([?&])(?:utm_[^&#]*&?)+".to_string(),
+                replace: "$1".to_string(),
+            },
+            RewriteRule::Regex {
+                pattern: r"[?&](#|$)".to_string(),
+                replace: "$1".to_string(),
+            },
+            RewriteRule::Regex {
+                pattern: "(".to_string(),
+                replace: String::new(),
+            },
+        ])
+    }
+
+    #[test]
+    fn host_swaps() {
+        let rewrite = rewriter().apply("https://www.reddit.com/r/rust/").unwrap();
+        assert_eq!(rewrite.rewritten, "https://old.reddit.com/r/rust/");
+        assert_eq!(rewrite.applied, ["reddit.com \u{2192} old.reddit.com"]);
+        assert_eq!(
+            rewriter()
+                .apply("https://twitter.com/a/status/1")
+                .unwrap()
+                .rewritten,
+            "https://nitter.local/a/status/1"
+        );
+        assert!(rewriter().apply("https://notreddit.com/").is_none());
+    }
+
+    #[test]
+    fn https_and_regex_chain() {
+        let rewrite = rewriter()
+            .apply("http://docs.example.com/page?utm_source=mail")
+            .unwrap();
+        assert_eq!(
+            rewrite.original,
+            "http://docs.example.com/page?utm_source=mail"
+        );
+        assert_eq!(rewrite.rewritten, "https://docs.example.com/page");
+        assert_eq!(rewrite.applied.len(), 3);
+        assert!(rewriter().apply("http://example.org/").is_none());
+    }
+
+    #[test]
+    fn regex_replaces_every_match() {
+        let rewritten = |url: &str| rewriter().apply(url).unwrap().rewritten;
+        assert_eq!(
+            rewritten("https://x.org/page?utm_a=1&utm_b=2&id=5"),
+            "https://x.org/page?id=5"
+        );
+        assert_eq!(
+            rewritten("https://x.org/page?utm_x=1&id=5"),
+            "https://x.org/page?id=5"
+        );
+        assert_eq!(
+            rewritten("https://x.org/page?id=5&utm_a=1&q=2&utm_b=3#top"),
+            "https://x.org/page?id=5&q=2#top"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::{Error, ErrorKind};
 use std::path::Path;
@@ -9,28 +10,171 @@ pub struct BrowserInfo {
     pub name: String,
     pub path: String,
     pub cmd: Vec<String>,
+    #[serde(flatten)]
+    pub launch: LaunchOptions,
+}
+
+/// Card that opens the URL in several browsers at once
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct BrowserGroup {
+    pub name: String,
+    pub members: Vec<String>, // Browser names, in launch order
+}
+
+/// How the browser process is started, next to the executable and arguments
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Default, Debug)]
+pub struct LaunchOptions {
+    /// Working directory, the current one when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Variables to set, a null value removes the variable instead
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, Option<String>>,
+    /// Start in a new process group without the console of url_proxy
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub detached: bool,
+    /// Never create a console window, for console based launchers
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_window: bool,
+    /// Refuses `file:` URLs, the picker warns before opening local files with it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_file_urls: bool,
+    /// Put `--` before the URL so it can't be taken for an option, if the browser understands it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub end_of_options: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct Settings {
     pub browsers: Vec<BrowserInfo>,
+    #[serde(default)]
+    pub handlers: BTreeMap<String, Vec<BrowserInfo>>, // Candidates per URL scheme, `browsers` otherwise
+    #[serde(default)]
+    pub groups: Vec<BrowserGroup>,
+    #[serde(default = "default_allowed_schemes")]
+    pub allowed_schemes: Vec<String>, // Schemes in `handlers` are allowed as well
+    #[serde(default)]
+    pub allow_executable_files: bool,
+    #[serde(default)]
+    pub routes: Vec<crate::source::RouteRule>, // Browsers picked by the application that opened the link
+    #[serde(default)]
+    pub rewrites: Vec<crate::rewrite::RewriteRule>, // Applied in order before display and launch
+    #[serde(default)]
+    pub blocklists: Vec<String>, // Hosts or plain domain files, relative to the executable
+    #[serde(default)]
+    pub blocklist_action: crate::blocklist::BlockAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<BrowserInfo>, // Opens local files instead of a browser, Notepad when unset
     pub cols: usize,
     pub rows: usize,
     pub cache_expire_days: usize,
+    #[serde(default)]
+    pub cache_backend: crate::cache::CacheBackend,
+    #[serde(default)]
+    pub order_by_usage: bool,
+    #[serde(default = "default_history_limit")]
+    pub history_limit: usize,
+    #[serde(default)]
+    pub theme: crate::theme::Theme,
+    #[serde(default)]
+    pub palette: crate::theme::Palette,
+    #[serde(default)]
+    pub layout: crate::mainwindow::Layout,
+    #[serde(default = "default_card_width")]
+    pub card_width: usize,
+    #[serde(default = "default_card_height")]
+    pub card_height: usize,
+    #[serde(default)]
+    pub placement: crate::placement::Placement,
+    #[serde(default)]
+    pub timeout_secs: usize,
+    #[serde(default)]
+    pub default_browser: Option<String>,
+    #[serde(default)]
+    pub focus_loss: crate::mainwindow::FocusLoss,
+}
+
+fn default_card_width() -> usize {
+    60
+}
+
+fn default_card_height() -> usize {
+    90
+}
+
+fn default_allowed_schemes() -> Vec<String> {
+    crate::link::DEFAULT_ALLOWED_SCHEMES
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+fn default_history_limit() -> usize {
+    10000
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
             browsers: Vec::new(),
+            handlers: BTreeMap::new(),
+            groups: Vec::new(),
+            allowed_schemes: default_allowed_schemes(),
+            allow_executable_files: false,
+            routes: Vec::new(),
+            rewrites: Vec::new(),
+            blocklists: Vec::new(),
+            blocklist_action: crate::blocklist::BlockAction::default(),
+            editor: None,
             cols: 3,
             rows: 2,
             cache_expire_days: 7,
+            cache_backend: crate::cache::CacheBackend::default(),
+            order_by_usage: false,
+            history_limit: default_history_limit(),
+            theme: crate::theme::Theme::default(),
+            palette: crate::theme::Palette::default(),
+            layout: crate::mainwindow::Layout::default(),
+            card_width: default_card_width(),
+            card_height: default_card_height(),
+            placement: crate::placement::Placement::default(),
+            timeout_secs: 0,
+            default_browser: None,
+            focus_loss: crate::mainwindow::FocusLoss::default(),
         }
     }
 }
 
 impl Settings {
+    /// What may be launched, the allowlist plus every scheme with its own handlers
+    pub fn link_policy(&self) -> crate::link::Policy {
+        let mut schemes: Vec<String> = self
+            .allowed_schemes
+            .iter()
+            .chain(self.handlers.keys())
+            .map(|s| s.trim_end_matches(':').to_ascii_lowercase())
+            .collect();
+        schemes.sort();
+        schemes.dedup();
+        crate::link::Policy {
+            schemes,
+            executable_files: self.allow_executable_files,
+        }
+    }
+
+    /// Handlers configured for the scheme of `url`, falling back to the browser list
+    pub fn handlers_for(&self, url: &str) -> &[BrowserInfo] {
+        let handlers = crate::link::scheme(url).and_then(|scheme| {
+            self.handlers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(&scheme))
+        });
+        match handlers {
+            Some((_, handlers)) => handlers,
+            None => &self.browsers,
+        }
+    }
+
     pub fn load() -> Self {
         let path = crate::utils::settings_path();
         match Self::load_from_path(&path) {
@@ -105,3 +249,55 @@ impl Settings {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn launch_options_are_optional() {
+        let info: BrowserInfo = serde_json::from_str(
+            r#"{"name": "Firefox", "path": "firefox.exe", "cmd": [],
+                "cwd": "D:\\Portable", "env": {"MOZ_HEADLESS": null, "HOME": "D:\\Home"}}"#,
+        )
+        .unwrap();
+        assert_eq!(info.launch.cwd.as_deref(), Some("D:\\Portable"));
+        assert_eq!(info.launch.env["MOZ_HEADLESS"], None);
+        assert_eq!(info.launch.env["HOME"].as_deref(), Some("D:\\Home"));
+        assert!(!info.launch.detached);
+
+        let plain: BrowserInfo =
+            serde_json::from_str(r#"{"name": "Edge", "path": "msedge.exe", "cmd": []}"#).unwrap();
+        assert_eq!(plain.launch, LaunchOptions::default());
+        assert!(!serde_json::to_string(&plain).unwrap().contains("env"));
+    }
+
+    #[test]
+    fn handlers_follow_the_scheme() {
+        let browser = |name: &str| BrowserInfo {
+            name: name.to_string(),
+            path: format!("{name}.exe"),
+            cmd: Vec::new(),
+            launch: LaunchOptions::default(),
+        };
+        let mut settings = Settings {
+            browsers: vec![browser("firefox")],
+            ..Settings::default()
+        };
+        settings
+            .handlers
+            .insert("MailTo".to_string(), vec![browser("thunderbird")]);
+        settings
+            .handlers
+            .insert("slack".to_string(), vec![browser("slack")]);
+
+        let names = |url: &str| -> Vec<String> {
+            let handlers = settings.handlers_for(url);
+            handlers.iter().map(|b| b.name.clone()).collect()
+        };
+        assert_eq!(names("mailto:someone@example.com"), ["thunderbird"]);
+        assert_eq!(names("slack://open?team=T1"), ["slack"]);
+        assert_eq!(names("https://example.com"), ["firefox"]);
+        assert_eq!(names("C:\\pages\\index.html"), ["firefox"]);
+    }
+}
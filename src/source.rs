@@ -0,0 +1,192 @@
+use crate::blocklist::Blocklist;
+use crate::history::{self, HistoryEntry, Routing};
+use crate::mainwindow::BrowserExec;
+use crate::settings::Settings;
+use serde::{Deserialize, Serialize};
+
+// Programs that only pass a link on, the application behind them is the real source
+const LAUNCHERS: &[&str] = &[
+    "url_proxy",
+    "explorer",
+    "svchost",
+    "sihost",
+    "runtimebroker",
+    "rundll32",
+    "openwith",
+    "cmd",
+    "conhost",
+    "powershell",
+    "pwsh",
+];
+
+// How far up the process tree to look
+const MAX_DEPTH: u32 = 8;
+
+/// Send links from an application straight to a browser, without the picker
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct RouteRule {
+    pub source: String, // Executable name of the application, like `slack` or `Discord.exe`
+    pub browser: String, // Name of a browser in the handler list for the URL
+}
+
+impl RouteRule {
+    pub fn matches(&self, app: &str) -> bool {
+        app_name(&self.source) == app
+    }
+}
+
+/// Lowercase file name without directory and `.exe`
+fn app_name(path: &str) -> String {
+    let name = path.rsplit('\\').next().unwrap_or(path).to_lowercase();
+    match name.strip_suffix(".exe") {
+        Some(stem) => stem.to_string(),
+        None => name,
+    }
+}
+
+/// First process that isn't a launcher, the candidates are ordered closest first
+fn pick_source(candidates: impl IntoIterator<Item = String>) -> Option<String> {
+    candidates
+        .into_iter()
+        .map(|candidate| app_name(&candidate))
+        .find(|name| !name.is_empty() && !LAUNCHERS.contains(&name.as_str()))
+}
+
+/// Application that opened the link, from the parent processes and then the foreground window
+pub fn source_app() -> Option<String> {
+    let ancestors = crate::utils::get_process_ancestry(MAX_DEPTH);
+    pick_source(
+        ancestors
+            .into_iter()
+            .chain(crate::utils::get_foreground_process_image()),
+    )
+}
+
+/// Rewritten and canonical form of `url` if it can be opened without the picker.
+/// None for refused, blocklisted and lookalike URLs, the picker explains those.
+fn routable_url(
+    url: &str,
+    settings: &Settings,
+    blocklist: &Blocklist,
+    app: &str,
+) -> Option<String> {
+    let url = match crate::rewrite::Rewriter::new(&settings.rewrites).apply(url) {
+        Some(rewrite) => rewrite.rewritten,
+        None => url.to_string(),
+    };
+    let url = match crate::link::canonicalize(&url, &settings.link_policy()) {
+        Ok(url) => url,
+        Err(e) => {
+            log::warn!("Not routing {url} from {app}: {e}");
+            return None;
+        }
+    };
+    if let Some(found) = crate::link::host(&url).and_then(|host| blocklist.check(&host)) {
+        log::warn!(
+            "Not routing {url} from {app}, {} is on blocklist {}",
+            found.domain,
+            found.list
+        );
+        return None;
+    }
+    if let Some(info) = crate::link::inspect_host(&url).filter(|info| !info.warnings.is_empty()) {
+        log::warn!("Not routing {url} from {app}: {}", info.warnings.join(", "));
+        return None;
+    }
+    Some(url)
+}
+
+/// Open the URL in the browser a route names for `app`.
+/// Returns false to fall back to the picker, which also explains refused and suspicious URLs.
+pub fn open_routed(url: &str, settings: &Settings, app: &str) -> bool {
+    let Some(rule) = settings.routes.iter().find(|rule| rule.matches(app)) else {
+        return false;
+    };
+    let blocklist = Blocklist::load(&settings.blocklists);
+    let Some(url) = routable_url(url, settings, &blocklist, app) else {
+        return false;
+    };
+    let policy = settings.link_policy();
+    let handlers = settings.handlers_for(&url);
+    let Some(info) = handlers
+        .iter()
+        .find(|b| b.name.eq_ignore_ascii_case(&rule.browser))
+    else {
+        log::warn!("Route for {app} names unknown browser {}", rule.browser);
+        return false;
+    };
+
+    match BrowserExec::from_info(info).spawn(&url, &policy) {
+        Ok(url) => {
+            log::info!("Routed {url} from {app} to {}", info.name);
+            let entry = HistoryEntry::new(&url, &info.name, Routing::Automatic);
+            if let Err(e) = history::append(&entry, settings.history_limit) {
+                log::error!("Failed to write history: {e}");
+            }
+            let mut usage = crate::usage::Usage::load();
+            usage.record(&info.name, crate::link::host(&url).as_deref());
+            if let Err(e) = usage.save() {
+                log::error!("Failed to save usage: {e}");
+            }
+            true
+        }
+        Err(e) => {
+            log::error!("Failed to open {} for {app}: {e}", info.name);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn launchers_are_skipped() {
+        let chain = [
+            "C:\\Windows\\System32\\rundll32.exe",
+            "C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe",
+            "C:\\Users\\me\\AppData\\Local\\slack\\slack.exe",
+            "C:\\Windows\\explorer.exe",
+        ];
+        assert_eq!(
+            pick_source(chain.map(String::from)).as_deref(),
+            Some("slack")
+        );
+        let chain = [
+            "C:\\Windows\\explorer.exe",
+            "C:\\Apps\\Discord\\Discord.exe",
+        ];
+        assert_eq!(
+            pick_source(chain.map(String::from)).as_deref(),
+            Some("discord")
+        );
+        assert_eq!(
+            pick_source(["C:\\Windows\\System32\\cmd.exe".to_string()]),
+            None
+        );
+    }
+
+    #[test]
+    fn rules_match_executable_names() {
+        let rule = RouteRule {
+            source: "Slack.exe".to_string(),
+            browser: "Chrome Work".to_string(),
+        };
+        assert!(rule.matches("slack"));
+        assert!(!rule.matches("slacker"));
+    }
+
+    #[test]
+    fn suspicious_urls_go_to_the_picker() {
+        let settings = Settings::default();
+        let blocklist = Blocklist::default();
+        assert_eq!(
+            routable_url("https://example.com/a", &settings, &blocklist, "slack").as_deref(),
+            Some("https://example.com/a")
+        );
+        // Cyrillic а in front of Latin pple
+        assert!(routable_url("https://\u{430}pple.com/", &settings, &blocklist, "slack").is_none());
+        assert!(routable_url("javascript:alert(1)", &settings, &blocklist, "slack").is_none());
+    }
+}
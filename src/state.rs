@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::result::Result;
+
+/// Window geometry remembered between runs in `window.json`
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct WindowState {
+    #[serde(default)]
+    pub position: Option<(f32, f32)>, // Outer top-left in logical points
+    #[serde(default)]
+    pub scale: Option<f32>, // Pixels per point of the monitor `position` is on
+    #[serde(default)]
+    pub size: Option<(f32, f32)>, // Inner size in logical points
+    #[serde(default)]
+    pub default_size: Option<(f32, f32)>, // Size computed from settings when `size` was saved
+}
+
+impl WindowState {
+    pub fn load() -> Self {
+        let path = crate::utils::window_state_path();
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::error!("Failed to parse window state {}: {e}", path.display());
+                Self::default()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                log::error!("Failed to read window state {}: {e}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    /// Saved size, unless the layout settings changed since it was saved
+    pub fn size_for(&self, default_size: (f32, f32)) -> (f32, f32) {
+        match self.size {
+            Some(size) if self.default_size == Some(default_size) => size,
+            _ => default_size,
+        }
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let path = crate::utils::window_state_path();
+        let content = serde_json::to_string(self).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Failed to serialize window state: {}", e),
+            )
+        })?;
+        fs::write(&path, content).map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!("Failed to write window state to {}: {}", path.display(), e),
+            )
+        })
+    }
+}
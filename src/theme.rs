@@ -0,0 +1,66 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+/// Which visuals the window uses
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    /// Follow the light/dark preference of the operating system
+    System,
+}
+
+/// Custom colors on top of the theme, each one `#RRGGBB` or `#RRGGBBAA`
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct Palette {
+    #[serde(default)]
+    pub accent: Option<String>,
+    #[serde(default)]
+    pub card_background: Option<String>,
+    #[serde(default)]
+    pub hover: Option<String>,
+}
+
+/// Apply the theme and palette from settings to the context
+pub fn apply(ctx: &egui::Context, theme: Theme, palette: &Palette) {
+    ctx.set_theme(match theme {
+        Theme::Dark => egui::ThemePreference::Dark,
+        Theme::Light => egui::ThemePreference::Light,
+        Theme::System => egui::ThemePreference::System,
+    });
+
+    let accent = parse_color("accent", &palette.accent);
+    let card_background = parse_color("card_background", &palette.card_background);
+    let hover = parse_color("hover", &palette.hover);
+
+    // Both light and dark styles, so the palette survives system theme switches
+    ctx.all_styles_mut(|style| {
+        let visuals = &mut style.visuals;
+        if let Some(color) = accent {
+            visuals.selection.bg_fill = color;
+            visuals.selection.stroke.color = color;
+            visuals.hyperlink_color = color;
+        }
+        if let Some(color) = card_background {
+            visuals.widgets.inactive.weak_bg_fill = color;
+            visuals.widgets.inactive.bg_fill = color;
+        }
+        if let Some(color) = hover {
+            visuals.widgets.hovered.weak_bg_fill = color;
+            visuals.widgets.hovered.bg_fill = color;
+        }
+    });
+}
+
+fn parse_color(name: &str, value: &Option<String>) -> Option<egui::Color32> {
+    let value = value.as_ref()?;
+    match egui::Color32::from_hex(value.trim()) {
+        Ok(color) => Some(color),
+        Err(e) => {
+            log::warn!("Invalid {name} color {value:?} in palette: {e:?}");
+            None
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::result::Result;
+
+// How much a launch for the same host counts compared to a launch anywhere
+const HOST_WEIGHT: u64 = 5;
+// Hosts kept, the ones not launched for the longest time go first
+const MAX_HOSTS: usize = 1000;
+
+/// A launch counted in memory but not saved yet
+#[derive(Clone)]
+struct Launch {
+    browser: String,
+    host: Option<String>,
+    time: usize,
+}
+
+/// Launch counts used to put the most likely browser first
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Usage {
+    browsers: BTreeMap<String, u64>, // Launches per browser name
+    hosts: BTreeMap<String, BTreeMap<String, u64>>, // Launches per host, then per browser name
+    #[serde(default)]
+    last_used: BTreeMap<String, usize>, // Unix milliseconds of the latest launch per host
+    #[serde(skip)]
+    pending: Vec<Launch>, // Launches since the last save
+}
+
+impl Usage {
+    pub fn load() -> Self {
+        let path = crate::utils::usage_path();
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::error!("Failed to parse usage {}: {e}", path.display());
+                Self::default()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                log::error!("Failed to read usage {}: {e}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    /// Add the launches of this session to what other instances saved meanwhile and write the result
+    pub fn save(&mut self) -> Result<(), Error> {
+        let Some(_lock) = crate::utils::lock_with_retry(
+            &crate::utils::usage_lock_path(),
+            false,
+            crate::utils::LOCK_WAIT,
+        ) else {
+            return Err(Error::new(
+                ErrorKind::WouldBlock,
+                "Usage is locked by another instance",
+            ));
+        };
+        let mut merged = Self::load();
+        merged.merge(&self.pending);
+        self.pending.clear();
+        *self = merged;
+
+        let path = crate::utils::usage_path();
+        let content = serde_json::to_string(self).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Failed to serialize usage: {}", e),
+            )
+        })?;
+        fs::write(&path, content).map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!("Failed to write usage to {}: {}", path.display(), e),
+            )
+        })
+    }
+
+    /// Count a launch of `browser`, for `host` too when the URL has one
+    pub fn record(&mut self, browser: &str, host: Option<&str>) {
+        let launch = Launch {
+            browser: browser.to_string(),
+            host: host.map(str::to_string),
+            time: crate::utils::get_unix_msec(),
+        };
+        self.count(&launch);
+        self.pending.push(launch);
+    }
+
+    fn count(&mut self, launch: &Launch) {
+        *self.browsers.entry(launch.browser.clone()).or_default() += 1;
+        if let Some(ref host) = launch.host {
+            *self
+                .hosts
+                .entry(host.clone())
+                .or_default()
+                .entry(launch.browser.clone())
+                .or_default() += 1;
+            let last_used = self.last_used.entry(host.clone()).or_default();
+            *last_used = (*last_used).max(launch.time);
+        }
+    }
+
+    /// Count `launches` and drop the hosts beyond `MAX_HOSTS` that were used least recently
+    fn merge(&mut self, launches: &[Launch]) {
+        for launch in launches {
+            self.count(launch);
+        }
+        // Hosts from files without times sort first
+        let mut hosts: Vec<(usize, String)> = self
+            .hosts
+            .keys()
+            .map(|host| {
+                (
+                    self.last_used.get(host).copied().unwrap_or_default(),
+                    host.clone(),
+                )
+            })
+            .collect();
+        hosts.sort_unstable();
+        let excess = hosts.len().saturating_sub(MAX_HOSTS);
+        for (_, host) in hosts.into_iter().take(excess) {
+            self.hosts.remove(&host);
+            self.last_used.remove(&host);
+        }
+    }
+
+    /// Ranking of `browser` for a URL on `host`, higher is more likely
+    pub fn score(&self, browser: &str, host: Option<&str>) -> u64 {
+        let global = self.browsers.get(browser).copied().unwrap_or_default();
+        let for_host = host
+            .and_then(|host| self.hosts.get(host))
+            .and_then(|counts| counts.get(browser))
+            .copied()
+            .unwrap_or_default();
+        global + HOST_WEIGHT * for_host
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_launches_outweigh_global_ones() {
+        let mut usage = Usage::default();
+        for _ in 0..4 {
+            usage.record("Firefox", Some("example.com"));
+        }
+        usage.record("Chrome", Some("work.example.org"));
+        usage.record("Chrome", Some("work.example.org"));
+
+        assert!(usage.score("Firefox", None) > usage.score("Chrome", None));
+        assert!(
+            usage.score("Chrome", Some("work.example.org"))
+                > usage.score("Firefox", Some("work.example.org"))
+        );
+        assert_eq!(usage.score("Edge", Some("example.com")), 0);
+    }
+
+    #[test]
+    fn merge_keeps_other_instances_and_recent_hosts() {
+        // Saved by another instance since this one loaded the file
+        let mut stored = Usage::default();
+        for i in 0..MAX_HOSTS {
+            stored.count(&Launch {
+                browser: "Firefox".to_string(),
+                host: Some(format!("host{i}.example")),
+                time: i,
+            });
+        }
+
+        let mut usage = Usage::default();
+        usage.record("Chrome", Some("new.example"));
+        stored.merge(&usage.pending);
+
+        assert_eq!(stored.hosts.len(), MAX_HOSTS);
+        assert!(!stored.hosts.contains_key("host0.example"));
+        assert!(stored.hosts.contains_key("host1.example"));
+        assert_eq!(stored.score("Chrome", Some("new.example")), 1 + HOST_WEIGHT);
+        assert_eq!(stored.score("Firefox", None), MAX_HOSTS as u64);
+    }
+}
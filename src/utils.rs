@@ -1,5 +1,9 @@
+use std::fs::{File, OpenOptions, TryLockError};
 use std::os::windows::ffi::OsStrExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+pub const LOCK_WAIT: usize = 2000; // How long to wait for another instance to release a file
+pub const LOCK_RETRY_INTERVAL: u64 = 100; // Milliseconds between attempts while waiting
 
 pub fn exe_dir() -> PathBuf {
     match std::env::current_exe() {
@@ -35,10 +39,75 @@ pub fn cache_path() -> std::path::PathBuf {
     path_to("cache")
 }
 
+pub fn cache_lock_path() -> std::path::PathBuf {
+    path_to("cache.lock")
+}
+
+pub fn cache_instances_path() -> std::path::PathBuf {
+    path_to("cache.instances")
+}
+
+pub fn icon_dir_path() -> std::path::PathBuf {
+    path_to("icons")
+}
+
+pub fn usage_path() -> std::path::PathBuf {
+    path_to("usage.json")
+}
+
+pub fn usage_lock_path() -> std::path::PathBuf {
+    path_to("usage.lock")
+}
+
+pub fn history_path() -> std::path::PathBuf {
+    path_to("history.jsonl")
+}
+
+pub fn window_state_path() -> std::path::PathBuf {
+    path_to("window.json")
+}
+
 pub fn log_path() -> std::path::PathBuf {
     path_to("output.log")
 }
 
+/// Open `path` and lock it, shared or exclusively, retrying for up to `wait` milliseconds.
+/// None when another instance still holds it, other failures are logged.
+pub fn lock_with_retry(path: &Path, shared: bool, wait: usize) -> Option<File> {
+    let file = match OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+    {
+        Ok(file) => file,
+        Err(e) => {
+            log::warn!("Failed to open lock file {}: {e}", path.display());
+            return None;
+        }
+    };
+
+    let start = get_unix_msec();
+    loop {
+        let result = if shared {
+            file.try_lock_shared()
+        } else {
+            file.try_lock()
+        };
+        match result {
+            Ok(()) => return Some(file),
+            Err(TryLockError::WouldBlock) if get_unix_msec() - start < wait => {
+                std::thread::sleep(std::time::Duration::from_millis(LOCK_RETRY_INTERVAL));
+            }
+            Err(TryLockError::WouldBlock) => return None,
+            Err(TryLockError::Error(e)) => {
+                log::warn!("Failed to lock {}: {e}", path.display());
+                return None;
+            }
+        }
+    }
+}
+
 pub fn get_unix_msec() -> usize {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -57,6 +126,36 @@ extern "C" {
     fn FreeMemory(buf: *mut u8);
     fn OpenFile(path: *const u16) -> u32;
     fn GetScreenSize(width: *mut u64, height: *mut u64) -> u32;
+    fn AttachParentConsole() -> u32;
+    fn GetCursorPosition(x: *mut i64, y: *mut i64) -> u32;
+    fn GetWorkAreaAt(
+        x: i64,
+        y: i64,
+        left: *mut i64,
+        top: *mut i64,
+        right: *mut i64,
+        bottom: *mut i64,
+        dpi: *mut u32,
+    ) -> u32;
+    fn GetActiveWorkArea(
+        left: *mut i64,
+        top: *mut i64,
+        right: *mut i64,
+        bottom: *mut i64,
+        dpi: *mut u32,
+    ) -> u32;
+    fn GetProcessAncestry(buffer: *mut u16, slot: u32, max_depth: u32, count: *mut u32) -> u32;
+    fn GetForegroundProcessImage(buffer: *mut u16, size: u32) -> u32;
+}
+
+/// Usable area of a monitor in physical pixels, excluding taskbars
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorkArea {
+    pub left: i64,
+    pub top: i64,
+    pub right: i64,
+    pub bottom: i64,
+    pub scale: f32, // Monitor DPI relative to 96
 }
 
 pub fn extract_icon(path: &str) -> Option<image::RgbaImage> {
@@ -179,6 +278,104 @@ pub fn get_screen_size() -> (u64, u64) {
     (width, height)
 }
 
+pub fn get_cursor_position() -> Option<(i64, i64)> {
+    let mut x: i64 = 0;
+    let mut y: i64 = 0;
+    let result = unsafe { GetCursorPosition(&mut x, &mut y) };
+    if result != 0 {
+        log::error!("Error getting cursor position (error code: {result})");
+        return None;
+    }
+    Some((x, y))
+}
+
+/// Work area of the monitor nearest to a point in physical pixels
+pub fn get_work_area_at(x: i64, y: i64) -> Option<WorkArea> {
+    work_area_ffi(|left, top, right, bottom, dpi| unsafe {
+        GetWorkAreaAt(x, y, left, top, right, bottom, dpi)
+    })
+}
+
+/// Work area of the monitor with the foreground window
+pub fn get_active_work_area() -> Option<WorkArea> {
+    work_area_ffi(|left, top, right, bottom, dpi| unsafe {
+        GetActiveWorkArea(left, top, right, bottom, dpi)
+    })
+}
+
+fn work_area_ffi(
+    call: impl FnOnce(&mut i64, &mut i64, &mut i64, &mut i64, &mut u32) -> u32,
+) -> Option<WorkArea> {
+    let (mut left, mut top, mut right, mut bottom) = (0, 0, 0, 0);
+    let mut dpi: u32 = 96;
+    let result = call(&mut left, &mut top, &mut right, &mut bottom, &mut dpi);
+    if result != 0 {
+        log::error!("Error getting monitor work area (error code: {result})");
+        return None;
+    }
+    Some(WorkArea {
+        left,
+        top,
+        right,
+        bottom,
+        scale: dpi.max(1) as f32 / 96.0,
+    })
+}
+
+// Characters reserved for each process image path
+const PROCESS_IMAGE_LEN: usize = 1024;
+
+/// Image paths of up to `max_depth` parent processes, closest first
+pub fn get_process_ancestry(max_depth: u32) -> Vec<String> {
+    let mut buffer = vec![0u16; PROCESS_IMAGE_LEN * max_depth as usize];
+    let mut count = 0u32;
+    let result = unsafe {
+        GetProcessAncestry(
+            buffer.as_mut_ptr(),
+            PROCESS_IMAGE_LEN as u32,
+            max_depth,
+            &mut count,
+        )
+    };
+    if result != 0 {
+        log::warn!("Error getting parent processes (error code: {result})");
+        return Vec::new();
+    }
+    buffer
+        .chunks(PROCESS_IMAGE_LEN)
+        .take(count as usize)
+        .map(wide_to_string)
+        .collect()
+}
+
+/// Image path of the process owning the foreground window
+pub fn get_foreground_process_image() -> Option<String> {
+    let mut buffer = vec![0u16; PROCESS_IMAGE_LEN];
+    let result = unsafe { GetForegroundProcessImage(buffer.as_mut_ptr(), buffer.len() as u32) };
+    if result != 0 {
+        log::warn!("Error getting process image (error code: {result})");
+        return None;
+    }
+    Some(wide_to_string(&buffer))
+}
+
+/// Text of a NUL-terminated wide string buffer
+fn wide_to_string(buffer: &[u16]) -> String {
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..len])
+}
+
+/// Attach to the console of the calling shell so command line output is visible
+pub fn attach_console() -> bool {
+    let result = unsafe { AttachParentConsole() };
+    if result != 0 {
+        log::warn!("No parent console to attach to (error code: {result})");
+        false
+    } else {
+        true
+    }
+}
+
 mod tests {
 
     #[test]